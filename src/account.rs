@@ -4,7 +4,9 @@ use rust_decimal::prelude::*;
 
 type Updating<T> = watch::Receiver<Option<T>>;
 type Sender<T> = watch::Sender<Option<T>>;
-#[derive(Debug)]
+/// A position of the account portfolio with its market value and P&L.
+#[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Position {
     pub contract: contract::Contract,
     pub position: Option<Decimal>,
@@ -15,7 +17,7 @@ pub struct Position {
     pub realized_pnl: Option<Decimal>
 }
 
-pub struct AccountReceiver {
+pub(crate) struct AccountReceiver {
     pub update_time: Updating<String>,
     pub account_code: Updating<String>,
     pub account_type: Updating<String>,
@@ -29,7 +31,7 @@ pub struct AccountReceiver {
     pub portfolio: Updating<Vec<Position>>
 }
 
-pub struct AccountSender {
+pub(crate) struct AccountSender {
     pub update_time: Sender<String>,
     pub account_code: Sender<String>,
     pub account_type: Sender<String>,
@@ -43,7 +45,7 @@ pub struct AccountSender {
     pub portfolio: Sender<Vec<Position>>
 }
 
-pub fn init_account_channel() -> (AccountSender, AccountReceiver) {
+pub(crate) fn init_account_channel() -> (AccountSender, AccountReceiver) {
     let (update_time_t, update_time_r) = watch::channel(None);
     let (account_code_t, account_code_r) = watch::channel(None);
     let (account_type_t, account_type_r) = watch::channel(None);
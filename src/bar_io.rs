@@ -0,0 +1,279 @@
+// Export and import of bar series. All formats share the same columns: `time` (start of the bar, time zone aware),
+// `open`, `high`, `low`, `close`, `wap`, `volume` and `count`. CSV requires the `csv` feature, JSON the `serde`
+// feature and Arrow the `arrow` feature.
+use std::{error::Error, fmt};
+#[cfg(any(feature = "csv", feature = "serde"))]
+use chrono::{DateTime, SecondsFormat};
+#[cfg(any(feature = "csv", feature = "serde"))]
+use chrono_tz::Tz;
+
+#[derive(Debug)]
+struct BarFormatError(String);
+
+impl Error for BarFormatError {}
+
+impl fmt::Display for BarFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid bar data: {}", self.0) // user-facing output
+    }
+}
+
+fn format_error(reason: &str) -> Box<dyn Error> {
+    Box::new(BarFormatError(reason.to_string()))
+}
+
+#[cfg(any(feature = "csv", feature = "serde"))]
+fn parse_time(val: &str, tz: &Tz) -> Result<DateTime<Tz>, Box<dyn Error>> {
+    Ok(DateTime::parse_from_rfc3339(val)?.with_timezone(tz))
+}
+
+#[cfg(any(feature = "csv", feature = "serde"))]
+fn format_time(time: &DateTime<Tz>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, false)
+}
+
+#[cfg(feature = "serde")]
+mod json_io {
+    use std::convert::TryFrom;
+    use std::error::Error;
+    use std::io::{Read, Write};
+    use std::str::FromStr;
+    use chrono::DateTime;
+    use chrono_tz::Tz;
+    use rust_decimal::prelude::*;
+    use serde_json::{json, Value};
+    use crate::bars::{Bar, BarSeries};
+    use super::{format_error, format_time, parse_time};
+
+    fn json_to_decimal(val: &Value) -> Option<Decimal> {
+        match val {
+            Value::Number(num) => Decimal::from_str(&num.to_string()).ok()
+                .or_else(|| Decimal::from_scientific(&num.to_string()).ok()),
+            Value::String(num) => Decimal::from_str(num).ok(),
+            _ => None
+        }
+    }
+
+    impl BarSeries {
+        /// Writes the series as a JSON object with the fields `time_zone`, `start`, `end` and `bars`, an array of
+        /// objects with one field per column. `wap` and `volume` are written as strings to keep their exact value.
+        pub fn write_json<W: Write>(&self, writer: W) -> Result<(), Box<dyn Error>> {
+            let time_zone = self.data.first().map(|bar| bar.t_stamp.timezone())
+                .or_else(|| self.start_dt.map(|dt| dt.timezone()));
+            let bars: Vec<Value> = self.data.iter().map(|bar| json!({
+                "time": format_time(&bar.t_stamp),
+                "open": bar.open,
+                "high": bar.high,
+                "low": bar.low,
+                "close": bar.close,
+                "wap": bar.wap.to_string(),
+                "volume": bar.volume.to_string(),
+                "count": bar.count
+            })).collect();
+            let series = json!({
+                "time_zone": time_zone.map(|tz| tz.name()),
+                "start": self.start_dt.as_ref().map(format_time),
+                "end": self.end_dt.as_ref().map(format_time),
+                "bars": bars
+            });
+            serde_json::to_writer(writer, &series)?;
+            Ok(())
+        }
+        /// Reads a series written by `write_json`. Time stamps are localized in the stored time zone. `wap` and `volume`
+        /// may also be JSON numbers.
+        pub fn read_json<R: Read>(reader: R) -> Result<Self, Box<dyn Error>> {
+            let series: Value = serde_json::from_reader(reader)?;
+            let tz: Tz = match series["time_zone"].as_str() {
+                Some(name) => name.parse().map_err(|_| format_error("unknown time zone"))?,
+                None => chrono_tz::UTC
+            };
+            let time = |val: &Value| -> Result<Option<DateTime<Tz>>, Box<dyn Error>> {
+                match val.as_str() {
+                    Some(val) => Ok(Some(parse_time(val, &tz)?)),
+                    None => Ok(None)
+                }
+            };
+            let mut data = Vec::new();
+            for bar in series["bars"].as_array().ok_or_else(|| format_error("missing bars"))? {
+                let price = |column: &str| bar[column].as_f64().ok_or_else(|| format_error(column));
+                data.push(Bar {
+                    t_stamp: time(&bar["time"])?.ok_or_else(|| format_error("time"))?,
+                    open: price("open")?,
+                    high: price("high")?,
+                    low: price("low")?,
+                    close: price("close")?,
+                    wap: json_to_decimal(&bar["wap"]).ok_or_else(|| format_error("wap"))?,
+                    volume: json_to_decimal(&bar["volume"]).ok_or_else(|| format_error("volume"))?,
+                    count: bar["count"].as_i64().and_then(|count| i32::try_from(count).ok()).ok_or_else(|| format_error("count"))?
+                });
+            }
+            Ok(BarSeries {start_dt: time(&series["start"])?, end_dt: time(&series["end"])?, data})
+        }
+    }
+}
+
+#[cfg(feature = "csv")]
+mod csv_io {
+    use std::error::Error;
+    use std::io::{Read, Write};
+    use std::str::FromStr;
+    use chrono_tz::Tz;
+    use rust_decimal::prelude::*;
+    use crate::bars::{Bar, BarSeries};
+    use super::{format_error, format_time, parse_time};
+
+    const COLUMNS: [&str; 8] = ["time", "open", "high", "low", "close", "wap", "volume", "count"];
+
+    impl BarSeries {
+        /// Writes the bars as CSV with a header row. Time stamps are written in RFC 3339 format with the UTC offset
+        /// of the exchange time zone.
+        pub fn write_csv<W: Write>(&self, writer: W) -> Result<(), Box<dyn Error>> {
+            let mut writer = csv::Writer::from_writer(writer);
+            writer.write_record(COLUMNS)?;
+            for bar in &self.data {
+                writer.write_record(&[
+                    format_time(&bar.t_stamp),
+                    bar.open.to_string(),
+                    bar.high.to_string(),
+                    bar.low.to_string(),
+                    bar.close.to_string(),
+                    bar.wap.to_string(),
+                    bar.volume.to_string(),
+                    bar.count.to_string()
+                ])?;
+            }
+            writer.flush()?;
+            Ok(())
+        }
+        /// Reads bars written by `write_csv`. Time stamps are converted to the given time zone.
+        pub fn read_csv<R: Read>(reader: R, tz: &Tz) -> Result<Self, Box<dyn Error>> {
+            let mut reader = csv::Reader::from_reader(reader);
+            if reader.headers()?.iter().ne(COLUMNS.iter().copied()) {
+                return Err(format_error("unexpected CSV columns"));
+            }
+            let mut data = Vec::new();
+            for record in reader.records() {
+                let record = record?;
+                data.push(Bar {
+                    t_stamp: parse_time(&record[0], tz)?,
+                    open: record[1].parse()?,
+                    high: record[2].parse()?,
+                    low: record[3].parse()?,
+                    close: record[4].parse()?,
+                    wap: Decimal::from_str(&record[5])?,
+                    volume: Decimal::from_str(&record[6])?,
+                    count: record[7].parse()?
+                });
+            }
+            Ok(BarSeries {start_dt: None, end_dt: None, data})
+        }
+    }
+}
+
+#[cfg(feature = "arrow")]
+mod arrow {
+    use std::error::Error;
+    use std::sync::Arc;
+    use arrow_array::{Array, ArrayRef, Decimal128Array, Float64Array, Int32Array, RecordBatch, TimestampSecondArray};
+    use arrow_schema::{DataType, Field, Schema, TimeUnit};
+    use chrono::TimeZone;
+    use chrono_tz::Tz;
+    use rust_decimal::prelude::*;
+    use crate::bars::{Bar, BarSeries};
+    use super::format_error;
+
+    const DECIMAL_PRECISION: u8 = 38;
+    const DECIMAL_SCALE: i8 = 18;
+
+    /// Returns the value at `DECIMAL_SCALE`, rounding values with more decimal places.
+    fn to_decimal128(val: &Decimal) -> Result<i128, Box<dyn Error>> {
+        let val = val.round_dp(DECIMAL_SCALE as u32);
+        10i128.checked_pow(DECIMAL_SCALE as u32 - val.scale())
+            .and_then(|factor| val.mantissa().checked_mul(factor))
+            .filter(|val| val.unsigned_abs() < 10u128.pow(DECIMAL_PRECISION as u32))
+            .ok_or_else(|| format_error("decimal out of range"))
+    }
+
+    fn from_decimal128(mut val: i128) -> Result<Decimal, Box<dyn Error>> {
+        let mut scale = DECIMAL_SCALE as u32;
+        while scale > 0 && val % 10 == 0 {
+            val /= 10;
+            scale -= 1;
+        }
+        Ok(Decimal::try_from_i128_with_scale(val, scale)?)
+    }
+
+    impl BarSeries {
+        /// Returns the Arrow schema of `to_record_batch` for bars in the given time zone. `time` is a timestamp in
+        /// seconds, prices are 64 bit floats, `wap` and `volume` are decimals with 18 decimal places and `count` is a
+        /// 32 bit integer.
+        pub fn arrow_schema(tz: &Tz) -> Schema {
+            let float = |name| Field::new(name, DataType::Float64, false);
+            let decimal = |name| Field::new(name, DataType::Decimal128(DECIMAL_PRECISION, DECIMAL_SCALE), false);
+            Schema::new(vec![
+                Field::new("time", DataType::Timestamp(TimeUnit::Second, Some(tz.name().into())), false),
+                float("open"), float("high"), float("low"), float("close"), decimal("wap"), decimal("volume"),
+                Field::new("count", DataType::Int32, false)
+            ])
+        }
+        /// Converts the bars into an Arrow `RecordBatch`. The time zone is taken from the first bar (UTC if empty).
+        /// Fails if `wap` or `volume` do not fit into the decimal columns.
+        pub fn to_record_batch(&self) -> Result<RecordBatch, Box<dyn Error>> {
+            let tz = self.data.first().map_or(chrono_tz::UTC, |bar| bar.t_stamp.timezone());
+            let floats = |f: fn(&Bar) -> f64| -> ArrayRef {Arc::new(Float64Array::from_iter_values(self.data.iter().map(f)))};
+            let decimals = |f: fn(&Bar) -> &Decimal| -> Result<ArrayRef, Box<dyn Error>> {
+                let values = self.data.iter().map(|bar| to_decimal128(f(bar))).collect::<Result<Vec<i128>, _>>()?;
+                Ok(Arc::new(Decimal128Array::from_iter_values(values).with_precision_and_scale(DECIMAL_PRECISION, DECIMAL_SCALE)?))
+            };
+            let columns: Vec<ArrayRef> = vec![
+                Arc::new(TimestampSecondArray::from_iter_values(self.data.iter().map(|bar| bar.t_stamp.timestamp()))
+                    .with_timezone(tz.name())),
+                floats(|bar| bar.open),
+                floats(|bar| bar.high),
+                floats(|bar| bar.low),
+                floats(|bar| bar.close),
+                decimals(|bar| &bar.wap)?,
+                decimals(|bar| &bar.volume)?,
+                Arc::new(Int32Array::from_iter_values(self.data.iter().map(|bar| bar.count)))
+            ];
+            Ok(RecordBatch::try_new(Arc::new(BarSeries::arrow_schema(&tz)), columns)?)
+        }
+        /// Converts a `RecordBatch` with the schema of `to_record_batch` into a bar series.
+        pub fn from_record_batch(batch: &RecordBatch) -> Result<Self, Box<dyn Error>> {
+            fn column<'a, T: 'static>(batch: &'a RecordBatch, name: &str) -> Result<&'a T, Box<dyn Error>> {
+                batch.column_by_name(name)
+                    .and_then(|col| col.as_any().downcast_ref::<T>())
+                    .ok_or_else(|| format_error(name))
+            }
+            let time = column::<TimestampSecondArray>(batch, "time")?;
+            let tz: Tz = match time.timezone() {
+                Some(name) => name.parse().map_err(|_| format_error("unknown time zone"))?,
+                None => chrono_tz::UTC
+            };
+            let open = column::<Float64Array>(batch, "open")?;
+            let high = column::<Float64Array>(batch, "high")?;
+            let low = column::<Float64Array>(batch, "low")?;
+            let close = column::<Float64Array>(batch, "close")?;
+            let wap = column::<Decimal128Array>(batch, "wap")?;
+            let volume = column::<Decimal128Array>(batch, "volume")?;
+            if wap.scale() != DECIMAL_SCALE || volume.scale() != DECIMAL_SCALE {
+                return Err(format_error("unexpected decimal scale"));
+            }
+            let count = column::<Int32Array>(batch, "count")?;
+            let mut data = Vec::with_capacity(batch.num_rows());
+            for i in 0..batch.num_rows() {
+                data.push(Bar {
+                    t_stamp: tz.timestamp_opt(time.value(i), 0).single().ok_or_else(|| format_error("time"))?,
+                    open: open.value(i),
+                    high: high.value(i),
+                    low: low.value(i),
+                    close: close.value(i),
+                    wap: from_decimal128(wap.value(i))?,
+                    volume: from_decimal128(volume.value(i))?,
+                    count: count.value(i)
+                });
+            }
+            Ok(BarSeries {start_dt: None, end_dt: None, data})
+        }
+    }
+}
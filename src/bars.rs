@@ -1,20 +1,274 @@
+use tokio::sync::mpsc;
+use rust_decimal::Decimal;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
+use log::warn;
+use crate::client::RequestGuard;
 
+/// A price bar. The time stamp marks the start of the bar in the time zone of the exchange.
+/// Daily and longer bars are stamped at midnight of the trading date.
 #[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Bar {
+    #[cfg_attr(feature = "serde", serde(with = "serde_tz"))]
+    pub t_stamp: DateTime<Tz>,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub wap: Decimal,
+    pub volume: Decimal,
+    pub count: i32
+}
+/// A time-ordered series of price bars.
+#[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BarSeries {
+    #[cfg_attr(feature = "serde", serde(with = "serde_tz::option"))]
+    pub start_dt: Option<DateTime<Tz>>,
+    #[cfg_attr(feature = "serde", serde(with = "serde_tz::option"))]
+    pub end_dt: Option<DateTime<Tz>>,
+    pub data: Vec<Bar>
+}
+
+/// Serializes time stamps as RFC 3339 followed by the name of the time zone, e.g.
+/// `2023-03-01T09:30:00-05:00 US/Eastern`, so that they are deserialized in the time zone of the exchange.
+#[cfg(feature = "serde")]
+mod serde_tz {
+    use chrono::{DateTime, SecondsFormat};
+    use chrono_tz::Tz;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    fn format(t: &DateTime<Tz>) -> String {
+        format!("{} {}", t.to_rfc3339_opts(SecondsFormat::AutoSi, false), t.timezone().name())
+    }
+
+    pub fn serialize<S: Serializer>(t: &DateTime<Tz>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format(t))
+    }
+
+    fn parse<E: de::Error>(val: &str) -> Result<DateTime<Tz>, E> {
+        let (time, zone) = val.split_once(' ').ok_or_else(|| E::custom(format!("missing time zone in {}", val)))?;
+        let tz: Tz = zone.parse().map_err(E::custom)?;
+        Ok(DateTime::parse_from_rfc3339(time).map_err(E::custom)?.with_timezone(&tz))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Tz>, D::Error> {
+        parse(&String::deserialize(deserializer)?)
+    }
+
+    pub mod option {
+        use chrono::DateTime;
+        use chrono_tz::Tz;
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+        pub fn serialize<S: Serializer>(t: &Option<DateTime<Tz>>, serializer: S) -> Result<S::Ok, S::Error> {
+            t.as_ref().map(super::format).serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<DateTime<Tz>>, D::Error> {
+            Option::<String>::deserialize(deserializer)?.map(|val| super::parse(&val)).transpose()
+        }
+    }
+}
+
+impl BarSeries {
+    /// Merges a streaming bar update into the series. An update for the same time stamp as the last bar
+    /// replaces the in-progress bar, a later update closes the last bar and is appended. Updates of earlier bars
+    /// replace the bar with the same time stamp or are inserted in time order.
+    fn merge(&mut self, bar: Bar) {
+        match self.data.last_mut() {
+            Some(last) if last.t_stamp == bar.t_stamp => *last = bar,
+            Some(last) if last.t_stamp > bar.t_stamp => match self.data.binary_search_by(|b| b.t_stamp.cmp(&bar.t_stamp)) {
+                Ok(i) => self.data[i] = bar,
+                Err(i) => self.data.insert(i, bar)
+            },
+            _ => self.data.push(bar)
+        }
+    }
+}
+
+/// Parses a date string as sent by the TWS: epoch seconds, a trading date `yyyymmdd` or a date time
+/// `yyyymmdd hh:mm:ss` with an optional time zone suffix. Dates and date times without a time zone are
+/// interpreted in the given time zone.
+pub(crate) fn parse_tws_time(val: &str, tz: &Tz) -> Option<DateTime<Tz>> {
+    let val = val.trim();
+    if val.len() == 8 {
+        let date = NaiveDate::parse_from_str(val, "%Y%m%d").ok()?;
+        return tz.from_local_datetime(&date.and_hms_opt(0, 0, 0)?).earliest();
+    }
+    if let Ok(epoch) = val.parse::<i64>() {
+        return tz.timestamp_opt(epoch, 0).single();
+    }
+    let mut parts = val.split_whitespace();
+    let date = parts.next()?;
+    let time = parts.next()?;
+    let local = NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y%m%d %H:%M:%S").ok()?;
+    match parts.next() {
+        Some(zone) => {
+            let zone: Tz = zone.parse().ok()?;
+            Some(zone.from_local_datetime(&local).earliest()?.with_timezone(tz))
+        },
+        None => tz.from_local_datetime(&local).earliest()
+    }
+}
+
+/// A bar as decoded from the socket, before the time stamp is localized.
+#[derive(Debug,Clone)]
+pub(crate) struct RawBar {
     pub t_stamp: Option<String>,
     pub open: Option<f64>,
     pub high: Option<f64>,
     pub low: Option<f64>,
     pub close: Option<f64>,
-    pub wap: Option<f64>,
-    pub volume: Option<i64>,
-    pub count: Option<isize>
+    pub wap: Option<Decimal>,
+    pub volume: Option<Decimal>,
+    pub count: Option<i32>
+}
+
+impl RawBar {
+    pub(crate) fn localize(self, tz: &Tz) -> Option<Bar> {
+        Some(Bar {
+            t_stamp: parse_tws_time(self.t_stamp.as_ref()?, tz)?,
+            open: self.open?,
+            high: self.high?,
+            low: self.low?,
+            close: self.close?,
+            wap: self.wap.unwrap_or_default(),
+            volume: self.volume.unwrap_or_default(),
+            count: self.count.unwrap_or(-1)
+        })
+    }
 }
+
+/// A bar series as decoded from the socket, before the time stamps are localized.
 #[derive(Debug,Clone)]
-pub struct BarSeries {
+pub(crate) struct RawBarSeries {
     pub start_dt: Option<String>,
     pub end_dt: Option<String>,
-    pub n_bars: Option<usize>,
-    pub data: Option<Vec<Bar>>
+    pub data: Vec<RawBar>
+}
+
+impl RawBarSeries {
+    pub(crate) fn localize(self, tz: &Tz) -> BarSeries {
+        let n_raw = self.data.len();
+        let data: Vec<Bar> = self.data.into_iter().filter_map(|bar| bar.localize(tz)).collect();
+        if data.len() < n_raw {
+            warn!("{} incomplete bars dropped from bar series.", n_raw - data.len());
+        }
+        BarSeries {
+            start_dt: self.start_dt.and_then(|dt| parse_tws_time(&dt, tz)),
+            end_dt: self.end_dt.and_then(|dt| parse_tws_time(&dt, tz)),
+            data
+        }
+    }
+}
+
+/// A range of missing bars in a bar series. `start` is the time stamp of the first missing bar and `end` the
+/// end of the last missing bar.
+#[derive(Debug,Clone,PartialEq)]
+pub struct Gap {
+    pub start: DateTime<Tz>,
+    pub end: DateTime<Tz>,
+    pub missing_bars: usize
+}
+
+/// A price bucket of a histogram holding the size traded at that price.
+#[derive(Debug,Clone)]
+pub struct HistogramEntry {
+    pub price: Decimal,
+    pub size: Decimal
 }
 
+/// The `BarSubscription` is returned by a historical bar request with keepUpToDate enabled. It holds the
+/// initial bar series and receives streaming updates of the bar that is currently in progress.
+/// The subscription is cancelled when it is dropped.
+pub struct BarSubscription {
+    series: BarSeries,
+    tz: Tz,
+    updates_rx: mpsc::UnboundedReceiver<RawBar>,
+    _guard: RequestGuard
+}
+
+impl BarSubscription {
+    pub(crate) fn new(series: BarSeries, tz: Tz, updates_rx: mpsc::UnboundedReceiver<RawBar>, guard: RequestGuard) -> Self {
+        BarSubscription {series, tz, updates_rx, _guard: guard}
+    }
+    /// Cancels the subscription and returns the bar series with all updates received so far.
+    pub fn cancel(mut self) -> BarSeries {
+        self.update();
+        self.series
+    }
+    /// Waits for the next bar update, merges it into the series and returns it.
+    /// Returns `None` once the subscription was terminated by the TWS/Gateway or the client.
+    pub async fn next_bar(&mut self) -> Option<Bar> {
+        loop {
+            let raw = self.updates_rx.recv().await?;
+            if let Some(bar) = raw.localize(&self.tz) {
+                self.series.merge(bar.clone());
+                return Some(bar);
+            }
+        }
+    }
+    /// Merges all bar updates received so far into the series without waiting.
+    pub fn update(&mut self) {
+        while let Ok(raw) = self.updates_rx.try_recv() {
+            if let Some(bar) = raw.localize(&self.tz) {
+                self.series.merge(bar);
+            }
+        }
+    }
+    /// Returns the bar series including all merged updates.
+    pub fn series(&self) -> &BarSeries {
+        &self.series
+    }
+    /// Returns the most recent bar, which might still be in progress.
+    pub fn last_bar(&self) -> Option<&Bar> {
+        self.series.data.last()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Timelike;
+
+    fn series(bars: &[(u32, f64)]) -> BarSeries {
+        let tz = chrono_tz::US::Eastern;
+        BarSeries {start_dt: None, end_dt: None, data: bars.iter().map(|(minute, close)| Bar {
+            t_stamp: tz.with_ymd_and_hms(2023, 3, 1, 9, 30 + minute, 0).unwrap(),
+            open: 100.0, high: 101.0, low: 99.0, close: *close,
+            wap: Decimal::new(100, 0), volume: Decimal::new(10, 0), count: 1
+        }).collect()}
+    }
+
+    fn merged(mut base: BarSeries, updates: BarSeries) -> Vec<(u32, f64)> {
+        for bar in updates.data {
+            base.merge(bar);
+        }
+        base.data.iter().map(|bar| (bar.t_stamp.minute() - 30, bar.close)).collect()
+    }
+
+    #[test]
+    fn merge_identical() {
+        let bars = [(0, 100.0), (1, 100.5), (2, 101.0)];
+        assert_eq!(merged(series(&bars), series(&bars)), bars.to_vec());
+    }
+
+    #[test]
+    fn merge_overlapping() {
+        //updates of the in-progress bar replace it, earlier bars are corrected in place
+        assert_eq!(merged(series(&[(0, 100.0), (1, 100.5)]), series(&[(1, 100.75), (2, 101.0), (2, 101.25)])),
+            vec![(0, 100.0), (1, 100.75), (2, 101.25)]);
+        assert_eq!(merged(series(&[(0, 100.0), (1, 100.5), (2, 101.0)]), series(&[(0, 99.5)])),
+            vec![(0, 99.5), (1, 100.5), (2, 101.0)]);
+    }
+
+    #[test]
+    fn merge_disjoint() {
+        assert_eq!(merged(series(&[(0, 100.0)]), series(&[(2, 101.0), (3, 101.5)])), vec![(0, 100.0), (2, 101.0), (3, 101.5)]);
+        assert_eq!(merged(series(&[(2, 101.0), (4, 102.0)]), series(&[(0, 100.0), (3, 101.5)])),
+            vec![(0, 100.0), (2, 101.0), (3, 101.5), (4, 102.0)]);
+        assert_eq!(merged(series(&[]), series(&[(1, 100.5)])), vec![(1, 100.5)]);
+    }
+}
@@ -0,0 +1,204 @@
+use std::{error::Error, fmt};
+use chrono::{DateTime, Duration, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone};
+use chrono_tz::Tz;
+
+#[derive(Debug)]
+struct TradingHoursError(String);
+
+impl Error for TradingHoursError {}
+
+impl fmt::Display for TradingHoursError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid trading hours: {}", self.0) // user-facing output
+    }
+}
+
+fn hours_error(reason: String) -> Box<dyn Error> {
+    Box::new(TradingHoursError(reason))
+}
+
+/// A trading session. `ref_date` is the trading date the session belongs to, which differs from the calendar date
+/// of the start for overnight sessions.
+#[derive(Debug,Clone,PartialEq)]
+pub struct TradingSession {
+    pub start: DateTime<Tz>,
+    pub end: DateTime<Tz>,
+    pub ref_date: NaiveDate
+}
+
+/// The trading schedule of a contract as returned by `req_trading_schedule`, including holidays (no session)
+/// and early closes. All times are in the time zone of the exchange.
+#[derive(Debug,Clone)]
+pub struct TradingSchedule {
+    pub start: DateTime<Tz>,
+    pub end: DateTime<Tz>,
+    pub time_zone: Tz,
+    pub sessions: Vec<TradingSession>
+}
+
+/// Parses a schedule time stamp `yyyymmdd-hh:mm:ss` in the given time zone. Local times in a DST gap are
+/// moved forward by the length of the gap, see `localize`.
+pub(crate) fn parse_schedule_time(val: &str, tz: &Tz) -> Option<DateTime<Tz>> {
+    let local = NaiveDateTime::parse_from_str(val, "%Y%m%d-%H:%M:%S").ok()?;
+    localize(&local, tz)
+}
+
+/// Converts a local time to the given time zone. Ambiguous times resolve to the earlier instant, times in a DST gap
+/// are moved forward by the length of the gap (they are interpreted with the UTC offset before the gap), e.g.
+/// 02:30 becomes 03:30 in New York and 02:15 becomes 02:45 on Lord Howe Island, where the clocks skip 30 minutes.
+pub(crate) fn localize(local: &NaiveDateTime, tz: &Tz) -> Option<DateTime<Tz>> {
+    match tz.from_local_datetime(local) {
+        LocalResult::Single(time) | LocalResult::Ambiguous(time, _) => Some(time),
+        LocalResult::None => {
+            let before = tz.offset_from_utc_datetime(&(*local - Duration::days(1))).fix();
+            Some(before.from_local_datetime(local).single()?.with_timezone(tz))
+        }
+    }
+}
+
+/// Time zone IDs sent by the TWS that are not IANA IDs or that denote a fixed offset in the IANA database, but
+/// the time zone of an exchange including DST (Java time zone IDs). Java's `MST` has no DST, like Arizona.
+const TIME_ZONE_ALIASES: [(&str, &str); 17] = [
+    ("EST", "America/New_York"),
+    ("EDT", "America/New_York"),
+    ("CST", "America/Chicago"),
+    ("CDT", "America/Chicago"),
+    ("MST", "America/Phoenix"),
+    ("PST", "America/Los_Angeles"),
+    ("HST", "Pacific/Honolulu"),
+    ("GB", "Europe/London"),
+    ("JST", "Asia/Tokyo"),
+    ("KST", "Asia/Seoul"),
+    ("CTT", "Asia/Shanghai"),
+    ("HKT", "Asia/Hong_Kong"),
+    ("SGT", "Asia/Singapore"),
+    ("IST", "Asia/Kolkata"),
+    ("AET", "Australia/Sydney"),
+    ("AEST", "Australia/Sydney"),
+    ("NZST", "Pacific/Auckland"),
+];
+
+/// Parses an IANA or IB specific time zone ID as sent in `ContractDetails` and trading schedules.
+pub fn parse_time_zone(id: &str) -> Option<Tz> {
+    let id = id.trim();
+    let iana = TIME_ZONE_ALIASES.iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(id))
+        .map_or(id, |(_, iana)| *iana);
+    iana.parse().ok()
+}
+
+fn parse_hours_date(val: &str) -> Result<NaiveDate, Box<dyn Error>> {
+    match NaiveDate::parse_from_str(val, "%Y%m%d") {
+        Ok(date) if val.len() == 8 => Ok(date),
+        _ => Err(hours_error(format!("invalid date {}", val)))
+    }
+}
+
+/// Parses a point in time of a trading hours string, either `hhmm` on the given date or `yyyymmdd:hhmm`.
+/// `2400` denotes midnight at the end of the day.
+fn parse_hours_time(val: &str, date: &NaiveDate) -> Result<NaiveDateTime, Box<dyn Error>> {
+    let (date, time) = match val.split_once(':') {
+        Some((date, time)) => (parse_hours_date(date)?, time),
+        None => (*date, val)
+    };
+    if time == "2400" {
+        return Ok(date.and_time(NaiveTime::MIN) + Duration::days(1));
+    }
+    let time = NaiveTime::parse_from_str(time, "%H%M").map_err(|_| hours_error(format!("invalid time {}", time)))?;
+    Ok(date.and_time(time))
+}
+
+/// Parses the `trading_hours` or `liquid_hours` of `ContractDetails` into sessions in the given time zone.
+/// Both the legacy format `20090507:0700-1830,1830-2330;20090508:CLOSED` and the current format
+/// `20180323:0400-20180323:2000;20180324:CLOSED` are supported, including several sessions per day and overnight
+/// sessions. The reference date of a session is the date on which it ends.
+pub fn parse_trading_hours(hours: &str, tz: &Tz) -> Result<Vec<TradingSession>, Box<dyn Error>> {
+    let mut sessions = Vec::new();
+    for day in hours.split(';').map(str::trim).filter(|day| !day.is_empty()) {
+        let (date, ranges) = day.split_once(':').ok_or_else(|| hours_error(format!("missing date in {}", day)))?;
+        if ranges.contains("CLOSED") {continue}
+        let date = parse_hours_date(date)?;
+        for range in ranges.split(',') {
+            let (start, end) = range.split_once('-').ok_or_else(|| hours_error(format!("invalid session {}", range)))?;
+            let start = parse_hours_time(start, &date)?;
+            let mut end = parse_hours_time(end, &start.date())?;
+            if end <= start {
+                //legacy format overnight session
+                end += Duration::days(1);
+            }
+            let ref_date = (end - Duration::seconds(1)).date();
+            let start = localize(&start, tz).ok_or_else(|| hours_error(format!("invalid local time {}", start)))?;
+            let end = localize(&end, tz).ok_or_else(|| hours_error(format!("invalid local time {}", end)))?;
+            if start < end {
+                sessions.push(TradingSession {start, end, ref_date});
+            }
+        }
+    }
+    sessions.sort_by_key(|session| session.start);
+    Ok(sessions)
+}
+
+/// The trading calendar of a contract with the liquid (regular) and the extended trading sessions, e.g. from
+/// `ContractDetails::trading_calendar`. Queries select the sessions with `use_rth`; times may be given in any
+/// time zone and results are in the time zone of the exchange.
+#[derive(Debug,Clone)]
+pub struct TradingCalendar {
+    time_zone: Tz,
+    liquid: Vec<TradingSession>,
+    extended: Vec<TradingSession>
+}
+
+impl TradingCalendar {
+    pub fn new(time_zone: Tz, mut liquid: Vec<TradingSession>, mut extended: Vec<TradingSession>) -> Self {
+        liquid.sort_by_key(|session| session.start);
+        extended.sort_by_key(|session| session.start);
+        TradingCalendar {time_zone, liquid, extended}
+    }
+    /// Returns the time zone of the exchange.
+    pub fn time_zone(&self) -> Tz {
+        self.time_zone
+    }
+    /// Returns the liquid sessions if `use_rth` is set, otherwise the sessions including extended hours.
+    pub fn sessions(&self, use_rth: bool) -> &[TradingSession] {
+        if use_rth {&self.liquid} else {&self.extended}
+    }
+    /// Returns the session open at the given time.
+    pub fn session_at<T: TimeZone>(&self, at: &DateTime<T>, use_rth: bool) -> Option<&TradingSession> {
+        let at = at.with_timezone(&self.time_zone);
+        self.sessions(use_rth).iter().find(|session| session.start <= at && at < session.end)
+    }
+    /// Returns true if a session is open at the given time.
+    pub fn is_open<T: TimeZone>(&self, at: &DateTime<T>, use_rth: bool) -> bool {
+        self.session_at(at, use_rth).is_some()
+    }
+    /// Returns the sessions of a trading date, which is empty if the market is closed on that day. Days with a
+    /// break, e.g. at lunch, have several sessions.
+    pub fn session_for(&self, date: NaiveDate, use_rth: bool) -> &[TradingSession] {
+        let sessions = self.sessions(use_rth);
+        let first = sessions.iter().position(|session| session.ref_date == date).unwrap_or(sessions.len());
+        let n = sessions[first..].iter().take_while(|session| session.ref_date == date).count();
+        &sessions[first..first + n]
+    }
+    /// Returns the next open after the given time, `None` if it is beyond the known sessions.
+    pub fn next_open<T: TimeZone>(&self, after: &DateTime<T>, use_rth: bool) -> Option<DateTime<Tz>> {
+        let after = after.with_timezone(&self.time_zone);
+        self.sessions(use_rth).iter().map(|session| session.start).find(|start| *start > after)
+    }
+    /// Returns the next close after the given time, `None` if it is beyond the known sessions.
+    pub fn next_close<T: TimeZone>(&self, after: &DateTime<T>, use_rth: bool) -> Option<DateTime<Tz>> {
+        let after = after.with_timezone(&self.time_zone);
+        self.sessions(use_rth).iter().map(|session| session.end).find(|end| *end > after)
+    }
+    /// Returns the time until the current session closes, `None` if the market is closed.
+    pub fn time_to_close<T: TimeZone>(&self, at: &DateTime<T>, use_rth: bool) -> Option<Duration> {
+        let session = self.session_at(at, use_rth)?;
+        Some(session.end - at.with_timezone(&self.time_zone))
+    }
+    /// Returns the time until the market opens, zero if it is open and `None` if the open is beyond the known sessions.
+    pub fn time_to_open<T: TimeZone>(&self, at: &DateTime<T>, use_rth: bool) -> Option<Duration> {
+        if self.is_open(at, use_rth) {
+            return Some(Duration::zero());
+        }
+        Some(self.next_open(at, use_rth)? - at.with_timezone(&self.time_zone))
+    }
+}
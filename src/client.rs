@@ -10,7 +10,13 @@ use crate::account;
 use crate::order;
 use crate::ticker;
 use crate::bars;
+use crate::historical;
+use crate::ticks;
 use crate::opt_params;
+use crate::futures_chain;
+use crate::strategy;
+use crate::market_rule;
+use crate::calendar;
 use crate::frame::IBFrame;
 
 use std::collections::HashMap;
@@ -20,7 +26,7 @@ use std::{error::Error, fmt};
 use rust_decimal::prelude::*;
 
 use std::str;
-use chrono::{TimeZone, DateTime};
+use chrono::{TimeZone, DateTime, Utc};
 //use chrono::format::ParseError;
 //use tokio::task;
 use tokio::time;
@@ -45,13 +51,24 @@ use log::{debug, error, info, trace, warn, LevelFilter, SetLoggerError};
 enum Request {
     OrderID(oneshot::Sender<i32>),
     ReqWithID{id: i32, sender: oneshot::Sender<Response>},
+    BarSubscription{id: i32, sender: oneshot::Sender<Response>},
+    MarketRule{id: i32, sender: oneshot::Sender<Response>},
+    Cancel(i32),
+    CancelMarketRule(i32),
 }
 enum Response {
     ContractDetails(Vec<contract::ContractDetails>),
     Order(order::OrderTracker),
     Ticker(ticker::Ticker),
-    Bars(bars::BarSeries),
-    OptParams(opt_params::OptParams),
+    Bars(bars::RawBarSeries),
+    BarSubscription(bars::RawBarSeries, mpsc::UnboundedReceiver<bars::RawBar>),
+    HistoricalTicks(ticks::HistoricalTicks),
+    HeadTimestamp(DateTime<Utc>),
+    Histogram(Vec<bars::HistogramEntry>),
+    TradingSchedule(calendar::TradingSchedule),
+    MarketRule(market_rule::MarketRule),
+    OptParams(Vec<opt_params::OptParams>),
+    ContractDescriptions(contract::ContractDescriptionList),
     TWSError(TWSError),
     Empty
 }
@@ -61,6 +78,9 @@ enum TaskState {
     Dead
 }
 
+/// Market rule responses and errors carry no request ID, so market rule requests time out instead of waiting forever.
+const MARKET_RULE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
 #[derive(Debug)]
 struct ResponseError;
 
@@ -90,6 +110,10 @@ impl TWSError {
     fn new(code: Option<i32>, message: Option<String>) -> TWSError {
         TWSError {code, message}
     }
+    /// Returns `true` if a historical data query found no data in the requested range.
+    fn is_no_data(&self) -> bool {
+        self.code == Some(162) && self.message.as_ref().is_some_and(|msg| msg.contains("returned no data"))
+    }
 }
 
 
@@ -103,6 +127,38 @@ impl fmt::Display for HandShakeError {
     }
 }
 #[derive(Debug)]
+struct ServerVersionError;
+impl Error for ServerVersionError {}
+impl fmt::Display for ServerVersionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The request is not supported by the server version of the TWS/Gateway.") // user-facing output
+    }
+}
+#[derive(Debug)]
+struct PriceRuleError;
+impl Error for PriceRuleError {}
+impl fmt::Display for PriceRuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Neither a market rule nor a minimum tick is available for the contract on the exchange.") // user-facing output
+    }
+}
+#[derive(Debug)]
+struct UnknownContractError;
+impl Error for UnknownContractError {}
+impl fmt::Display for UnknownContractError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "No contract matches the contract specification.") // user-facing output
+    }
+}
+#[derive(Debug)]
+struct AmbiguousContractError(Vec<String>);
+impl Error for AmbiguousContractError {}
+impl fmt::Display for AmbiguousContractError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The contract specification is ambiguous, it matches {}.", self.0.join(", ")) // user-facing output
+    }
+}
+#[derive(Debug)]
 struct SocketError;
 impl Error for SocketError {}
 impl fmt::Display for SocketError {
@@ -110,6 +166,34 @@ impl fmt::Display for SocketError {
         write!(f, "Socket connection to TWS/Gateway is dead.") // user-facing output
     }
 }
+/// Cancels a pending request when dropped, unless it was disarmed after the response was received. The reader task
+/// drops the pending entry and the cancel message is sent to the TWS/Gateway. Cancel messages are queued on an
+/// unbounded channel, which the writer task drains before other messages, so that they are never dropped.
+pub(crate) struct RequestGuard {
+    id: i32,
+    cancel_msg: String,
+    cancel_tx: mpsc::UnboundedSender<String>,
+    req_tx: crossbeam::channel::Sender<Request>,
+    armed: bool
+}
+
+impl RequestGuard {
+    fn disarm(mut self) {
+        self.armed = false;
+    }
+}
+
+impl Drop for RequestGuard {
+    fn drop(&mut self) {
+        if self.armed {
+            let _ = self.req_tx.send(Request::Cancel(self.id));
+            if self.cancel_tx.send(std::mem::take(&mut self.cancel_msg)).is_err() {
+                warn!("Cancel message for request {} could not be sent, the connection is closed.", self.id);
+            }
+        }
+    }
+}
+
 /// The client is used to make requests and receive account updates.
 pub struct IBClient
 {
@@ -118,6 +202,7 @@ pub struct IBClient
     reader_abort_handle: AbortHandle,
     keep_alive_abort_handle: AbortHandle,
     write_tx: mpsc::Sender<String>,
+    cancel_tx: mpsc::UnboundedSender<String>,
     req_tx: crossbeam::channel::Sender<Request>,
     reader_state_rx: watch::Receiver<Option<TaskState>>,
     writer_state_rx: watch::Receiver<Option<TaskState>>,
@@ -126,6 +211,11 @@ pub struct IBClient
     next_req_id: i32,
     next_order_id: i32,
     mkt_data_setting: MarketDataType,
+    market_rules: HashMap<i32, market_rule::MarketRule>,
+    contract_details: HashMap<String, contract::ContractDetails>,
+    contract_cache: contract::ContractCache,
+    hist_pacer: historical::Pacer,
+    hist_store: Option<historical::HistoricalStore>,
     log_handle: log4rs::Handle
 }
 
@@ -234,7 +324,16 @@ impl IBClient
 
     async fn make_request(&mut self, id: i32, msg: String) -> AsyncResult<(Response)> {
         let (resp_tx, resp_rx) = oneshot::channel();
-        self.req_tx.send(Request::ReqWithID{id, sender: resp_tx})?;
+        self.submit_request(Request::ReqWithID{id, sender: resp_tx}, resp_rx, msg).await
+    }
+
+    async fn make_bar_subscription(&mut self, id: i32, msg: String) -> AsyncResult<Response> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        self.submit_request(Request::BarSubscription{id, sender: resp_tx}, resp_rx, msg).await
+    }
+
+    async fn submit_request(&mut self, req: Request, resp_rx: oneshot::Receiver<Response>, msg: String) -> AsyncResult<Response> {
+        self.req_tx.send(req)?;
         self.write_tx.send(msg).await?;
         match resp_rx.await {
             Ok(response) => 
@@ -264,6 +363,7 @@ impl IBClient
         //set up required channels
         let (tx, mut rx) = mpsc::channel(64);
         let write_tx: mpsc::Sender<String> = tx.clone();
+        let (cancel_tx, mut cancel_rx) = mpsc::unbounded_channel::<String>();
         let (req_tx, req_rx) = channel::bounded(100);
         let (account_tx, account) = account::init_account_channel();
         let (reader_state_tx, reader_state_rx) = watch::channel(Some(TaskState::Running));
@@ -280,13 +380,21 @@ impl IBClient
             let mut positions_cache= Vec::new();
             let mut contract_details_cache: HashMap<i32,Vec<ContractDetails>> = HashMap::new();
             let mut executions_cache = HashMap::new();
+            let mut historical_ticks_cache: HashMap<i32, ticks::HistoricalTicks> = HashMap::new();
+            let mut opt_params_cache: HashMap<i32, Vec<opt_params::OptParams>> = HashMap::new();
             //pending requests
             let mut order_id_reqs = VecDeque::new();
             let mut requests = HashMap::new();
+            let mut bar_subscription_reqs = HashMap::new();
+            //market rule requests are keyed by the rule id instead of a request id, concurrent requests for the same
+            //rule are all answered by the first response
+            let mut market_rule_reqs: HashMap<i32, Vec<oneshot::Sender<Response>>> = HashMap::new();
             //open order trackers
             let mut order_trackers = HashMap::new();
             //open tickers
             let mut tickers = HashMap::new();
+            //open bar subscriptions
+            let mut bar_subscriptions = HashMap::new();
 
 
             loop {
@@ -309,7 +417,27 @@ impl IBClient
                             Request::OrderID(sender) => {
                                 order_id_reqs.push_back(sender)},
                             Request::ReqWithID{id,sender} => {
-                                requests.insert(id, sender);}
+                                requests.insert(id, sender);},
+                            Request::BarSubscription{id,sender} => {
+                                bar_subscription_reqs.insert(id, sender);},
+                            Request::MarketRule{id,sender} => {
+                                market_rule_reqs.entry(id).or_default().push(sender);},
+                            Request::Cancel(id) => {
+                                requests.remove(&id);
+                                bar_subscription_reqs.remove(&id);
+                                bar_subscriptions.remove(&id);
+                                historical_ticks_cache.remove(&id);
+                                opt_params_cache.remove(&id);
+                            }
+                            //drops the senders of timed out requests, other requests for the rule keep waiting
+                            Request::CancelMarketRule(id) => {
+                                if let Some(senders) = market_rule_reqs.get_mut(&id) {
+                                    senders.retain(|sender| !sender.is_closed());
+                                    if senders.is_empty() {
+                                        market_rule_reqs.remove(&id);
+                                    }
+                                }
+                            }
                         },
                         Err(_) => break
                     }
@@ -548,17 +676,80 @@ impl IBClient
                             if let Some((_, req)) = requests.remove_entry(&id) {
                                 let _ = req.send(Response::Bars(data));
                             }
+                            else if let Some((_, req)) = bar_subscription_reqs.remove_entry(&id) {
+                                let (updates_tx, updates_rx) = mpsc::unbounded_channel();
+                                if let Ok(()) = req.send(Response::BarSubscription(data, updates_rx)) {
+                                    bar_subscriptions.insert(id, updates_tx);
+                                }
+                            }
+                        },
+                        IBFrame::BarUpdate{id, bar} => {
+                            if let Some(updates_tx) = bar_subscriptions.get(&id) {
+                                if updates_tx.send(bar).is_err() {
+                                    bar_subscriptions.remove(&id);  //subscription dead
+                                }
+                            }
+                        },
+                        IBFrame::HistoricalTicks{id, ticks, done} => {
+                            let ticks = match historical_ticks_cache.remove(&id) {
+                                Some(mut cached) => {
+                                    cached.append_page(ticks, 0, &DateTime::<Utc>::MAX_UTC);
+                                    cached
+                                },
+                                None => ticks
+                            };
+                            if done {
+                                if let Some((_, req)) = requests.remove_entry(&id) {
+                                    let _ = req.send(Response::HistoricalTicks(ticks));
+                                }
+                            }
+                            else {
+                                historical_ticks_cache.insert(id, ticks);
+                            }
+                        },
+                        IBFrame::HeadTimestamp{id, time} => {
+                            if let Some((_, req)) = requests.remove_entry(&id) {
+                                let _ = req.send(Response::HeadTimestamp(time));
+                            }
+                        },
+                        IBFrame::Histogram{id, data} => {
+                            if let Some((_, req)) = requests.remove_entry(&id) {
+                                let _ = req.send(Response::Histogram(data));
+                            }
+                        },
+                        IBFrame::HistoricalSchedule{id, schedule} => {
+                            if let Some((_, req)) = requests.remove_entry(&id) {
+                                let _ = req.send(Response::TradingSchedule(schedule));
+                            }
+                        },
+                        IBFrame::MarketRule(rule) => {
+                            for req in market_rule_reqs.remove(&rule.id).unwrap_or_default() {
+                                let _ = req.send(Response::MarketRule(rule.clone()));
+                            }
                         },
                         IBFrame::OptParams{id, data} => {
+                            opt_params_cache.entry(id).or_default().push(data);
+                        },
+                        IBFrame::SymbolSamples{id, descriptions} => {
                             if let Some((_, req)) = requests.remove_entry(&id) {
-                                let _ = req.send(Response::OptParams(data));
+                                let _ = req.send(Response::ContractDescriptions(descriptions));
+                            }
+                        },
+                        IBFrame::OptParamsEnd(id) => {
+                            let params = opt_params_cache.remove(&id).unwrap_or_default();
+                            if let Some((_, req)) = requests.remove_entry(&id) {
+                                let _ = req.send(Response::OptParams(params));
                             }
                         }
                         IBFrame::Error{id, code, msg} => {
                             if let Some(idval) = id {
                                 match requests.remove_entry(&idval) {
                                     Some((_, tx)) => {let _ = tx.send(Response::TWSError(TWSError::new(code,msg)));},
-                                    None => ()
+                                    None => match bar_subscription_reqs.remove_entry(&idval) {
+                                        Some((_, tx)) => {let _ = tx.send(Response::TWSError(TWSError::new(code,msg)));},
+                                        //an error for a running subscription terminates it, closing the update stream
+                                        None => {bar_subscriptions.remove(&idval);}
+                                    }
                                 };
                             };
                             
@@ -580,12 +771,18 @@ impl IBClient
         let (writer_abort_handle, writer_abort_registration) = AbortHandle::new_pair();
         let writer_fut = Abortable::new(async move {
             loop {
-                match rx.recv().await {
-                    Some(msg) => match writer.write(&msg).await {
-                        Err(_) => {let _ = writer_state_tx.send(Some(TaskState::Dead)); return;}
-                        _ => info!("Message send: {msg}")
-                    },
-                    None => {let _ = writer_state_tx.send(Some(TaskState::Dead)); return;}
+                //cancel messages take precedence over requests
+                let msg = tokio::select! {
+                    biased;
+                    Some(msg) = cancel_rx.recv() => msg,
+                    msg = rx.recv() => match msg {
+                        Some(msg) => msg,
+                        None => {let _ = writer_state_tx.send(Some(TaskState::Dead)); return;}
+                    }
+                };
+                match writer.write(&msg).await {
+                    Err(_) => {let _ = writer_state_tx.send(Some(TaskState::Dead)); return;}
+                    _ => info!("Message send: {msg}")
                 }
             }
         }, writer_abort_registration);
@@ -610,6 +807,7 @@ impl IBClient
             reader_abort_handle,
             keep_alive_abort_handle,
             write_tx,
+            cancel_tx,
             req_tx,
             reader_state_rx,
             writer_state_rx,
@@ -618,6 +816,11 @@ impl IBClient
             next_req_id: 0,
             next_order_id,
             mkt_data_setting: MarketDataType::RealTime,
+            market_rules: HashMap::new(),
+            contract_details: HashMap::new(),
+            contract_cache: contract::ContractCache::new(),
+            hist_pacer: historical::Pacer::new(),
+            hist_store: None,
             log_handle
         };
         //subscribe to account updates
@@ -641,12 +844,27 @@ impl IBClient
     pub fn excess_liquidity(&self) -> Option<Decimal> {
         *self.account.excess_liquidity.borrow()
     }
+    /// Returns the positions of the account portfolio. If no portfolio was received yet, returns `None`.
+    pub fn portfolio(&self) -> Option<Vec<account::Position>> {
+        self.account.portfolio.borrow().clone()
+    }
 
     fn get_next_req_id(&mut self) -> i32 {
         self.next_req_id += 1;
         self.next_req_id
     }
 
+    fn request_guard(&self, id: i32, cancel_msg: String) -> RequestGuard {
+        RequestGuard {id, cancel_msg, cancel_tx: self.cancel_tx.clone(), req_tx: self.req_tx.clone(), armed: true}
+    }
+
+    fn historical_data_guard(&self, id: i32) -> RequestGuard {
+        let mut cancel_msg = Outgoing::CancelHistoricalData.encode();
+        cancel_msg.push_str("1\0"); //version
+        cancel_msg.push_str(&id.encode());
+        self.request_guard(id, cancel_msg)
+    }
+
     fn get_next_order_id(&mut self) -> i32 {
         self.next_order_id += 1;
         self.next_order_id
@@ -667,6 +885,69 @@ impl IBClient
             _ => Err(Box::new(ResponseError{}))
         }
     }
+    /// Sets the cache of qualified contracts used by `qualify`, e.g. a persisted cache or one shared with other clients.
+    pub fn set_contract_cache(&mut self, cache: contract::ContractCache) {
+        self.contract_cache = cache;
+    }
+    /// Returns the cache of qualified contracts.
+    pub fn contract_cache(&self) -> &contract::ContractCache {
+        &self.contract_cache
+    }
+    /// Qualifies the contract: the con_id and all fields that are not set are filled from the contract details.
+    /// Fails if no contract or several contracts match. Qualified contracts are cached by their specification.
+    pub async fn qualify(&mut self, contract: &mut contract::Contract) -> AsyncResult<()> {
+        if let Some(qualified) = self.contract_cache.get(contract) {
+            contract.fill_from(&qualified);
+            return Ok(());
+        }
+        let mut details = self.req_contract_details(contract).await?;
+        if details.len() > 1 {
+            let matches = details.iter()
+                .filter_map(|details| details.contract()?.local_symbol.clone())
+                .collect();
+            return Err(Box::new(AmbiguousContractError(matches)));
+        }
+        let details = details.pop().ok_or(UnknownContractError)?;
+        let qualified = details.contract().cloned().ok_or(UnknownContractError)?;
+        self.contract_details.insert(contract.encode(), details);
+        self.contract_cache.insert(contract, qualified.clone());
+        contract.fill_from(&qualified);
+        Ok(())
+    }
+    /// Qualifies the contracts one after the other and returns them in the same order. Fails on the first contract
+    /// that cannot be qualified.
+    pub async fn qualify_many(&mut self, contracts: &[contract::Contract]) -> AsyncResult<Vec<contract::Contract>> {
+        let mut qualified = Vec::with_capacity(contracts.len());
+        for contract in contracts {
+            let mut contract = contract.clone();
+            self.qualify(&mut contract).await?;
+            qualified.push(contract);
+        }
+        Ok(qualified)
+    }
+    /// Looks up the contracts with the given security ID, e.g. an ISIN, and returns them qualified. A security
+    /// listed on several exchanges yields one contract per listing.
+    pub async fn lookup_by_sec_id(&mut self, sec_id_type: SecIdType, sec_id: &str) -> AsyncResult<Vec<contract::Contract>> {
+        let contract = contract::Contract::security(sec_id_type, sec_id)?;
+        let details = self.req_contract_details(&contract).await?;
+        Ok(details.into_iter().filter_map(|details| details.contract().cloned()).collect())
+    }
+    /// Searches contracts whose symbol or company name starts with the pattern. The TWS returns up to 16 matches,
+    /// each with the security types of its derivatives.
+    pub async fn search_symbols(&mut self, pattern: &str) -> AsyncResult<contract::ContractDescriptionList> {
+        if !self.is_connected() {
+            return Err(Box::new(SocketError));
+        }
+        let mut msg = Outgoing::ReqMatchingSymbols.encode();
+        let id = self.get_next_req_id();
+        msg.push_str(&id.encode());
+        msg.push_str(&pattern.encode());
+        match self.make_request(id, msg).await? {
+            Response::ContractDescriptions(descriptions) => Ok(descriptions),
+            Response::TWSError(error) => Err(Box::new(error)),
+            _ => Err(Box::new(ResponseError{}))
+        }
+    }
     /// Places an order. An `OrderTracker` is returned which can be used to monitor the order execution.
     pub async fn place_order(&mut self, order: &order::Order) -> AsyncResult<order::OrderTracker> {
         if !self.is_connected() {
@@ -714,36 +995,136 @@ impl IBClient
             _ => Err(Box::new(ResponseError{}))
         }
     }
-    /// Requests historical price bar data.
+    /// Returns the details of the contract, which are requested once and cached by the client.
+    async fn cached_contract_details(&mut self, contract: &contract::Contract) -> Option<contract::ContractDetails> {
+        let key = contract.encode();
+        if let Some(details) = self.contract_details.get(&key) {
+            return Some(details.clone());
+        }
+        let details = self.req_contract_details(contract).await.ok()?.into_iter().next()?;
+        self.contract_details.insert(key, details.clone());
+        Some(details)
+    }
+    /// Returns the time zone of the exchange the contract is traded on, which is used to localize bar time stamps.
+    /// Falls back to UTC if the contract details are not available.
+    async fn exchange_time_zone(&mut self, contract: &contract::Contract) -> chrono_tz::Tz {
+        match self.cached_contract_details(contract).await.and_then(|details| details.time_zone()) {
+            Some(tz) => tz,
+            None => {
+                warn!("Time zone of contract {:?} not available, bar time stamps are in UTC.", contract.symbol());
+                chrono_tz::UTC
+            }
+        }
+    }
+    /// Sets a local store for historical bars, which is used by `req_historical_data` and `backfill_bars` to only
+    /// request missing data. Pass `None` to disable the store.
+    pub fn set_historical_store(&mut self, store: Option<historical::HistoricalStore>) {
+        self.hist_store = store;
+    }
+    /// Requests historical price bar data. Bar time stamps are localized in the time zone of the exchange.
+    /// Requests are paced to stay within the historical data limits of IB and validated against the documented
+    /// duration, bar size and data type limits before they are sent.
+    /// Dropping the returned future before it completes (e.g. with `tokio::time::timeout`) cancels the request.
+    /// Requests for continuous futures always end now, `end_date_time` is ignored.
+    /// If a `HistoricalStore` is set, the bars of the calendar span of the duration before `end_date_time` (see
+    /// `HistoricalDataDuration::max_span`) are loaded from the store and only missing ranges are requested. Errors of
+    /// the TWS are returned as without a store, errors reading the store are returned as `std::io::Error` and errors
+    /// writing it are logged.
     pub async fn req_historical_data<Tz: TimeZone> (&mut self, contract: &contract::Contract, end_date_time: &DateTime<Tz>, 
         duration: HistoricalDataDuration, bar_period: HistoricalDataBarSize, what_to_show: HistoricalDataType, use_rth: bool) -> AsyncResult<bars::BarSeries>
-        where
-        <Tz as TimeZone>::Offset: std::fmt::Display
+        {
+        if self.hist_store.is_some() && contract.sec_type != Some(SecType::ContinuousFuture) {
+            historical::validate_bars(contract.sec_type.as_ref(), &duration, &bar_period, Some(&what_to_show))?;
+            let end = end_date_time.with_timezone(&Utc);
+            let start = end - duration.max_span();
+            return match self.backfill(contract, &start, &end, bar_period, what_to_show, use_rth).await? {
+                (_, Some(error)) => Err(error),
+                (backfill, None) => Ok(backfill.series)
+            };
+        }
+        self.fetch_historical_data(contract, end_date_time, duration, bar_period, what_to_show, use_rth).await
+    }
+    /// Requests historical price bars from the TWS, bypassing the store.
+    async fn fetch_historical_data<Tz: TimeZone> (&mut self, contract: &contract::Contract, end_date_time: &DateTime<Tz>,
+        duration: HistoricalDataDuration, bar_period: HistoricalDataBarSize, what_to_show: HistoricalDataType, use_rth: bool) -> AsyncResult<bars::BarSeries>
         {
         if !self.is_connected() {
             return Err(Box::new(SocketError));
         }
+        historical::validate_bars(contract.sec_type.as_ref(), &duration, &bar_period, Some(&what_to_show))?;
+        self.hist_pacer.pace().await;
         let mut msg = Outgoing::ReqHistoricalData.encode();
         let id = self.get_next_req_id();
         msg.push_str(&id.encode());
         msg.push_str(&contract.encode_for_hist_data());
-        msg.push_str(&end_date_time.format("%Y%m%d %H:%M:%S").to_string().encode());
+        if contract.sec_type == Some(SecType::ContinuousFuture) {
+            msg.push('\0'); //continuous futures do not support an end date
+        }
+        else {
+            msg.push_str(&end_date_time.with_timezone(&Utc).format("%Y%m%d-%H:%M:%S").to_string().encode());
+        }
         msg.push_str(&bar_period.encode());
         msg.push_str(&duration.encode());
         msg.push_str(&use_rth.encode());
         msg.push_str(&what_to_show.encode());
-        msg.push_str("1\00\0\0");
-        match self.make_request(id, msg).await? {
-            Response::Bars(bars) => Ok(bars),
+        msg.push_str("2\00\0\0"); //epoch time stamps, no keepUpToDate, no chart options
+        let guard = self.historical_data_guard(id);
+        let response = self.make_request(id, msg).await?;
+        guard.disarm();
+        match response {
+            Response::Bars(bars) => Ok(bars.localize(&self.exchange_time_zone(contract).await)),
             Response::TWSError(error) => Err(Box::new(error)),
             _ => Err(Box::new(ResponseError{}))
         }
     }
+    /// Requests historical price bar data and subscribes to updates of the most recent bar (keepUpToDate).
+    /// The returned `BarSubscription` holds the initial bar series ending now and receives streaming updates,
+    /// which replace the in-progress last bar until it closes. The subscription is cancelled when it is dropped.
+    pub async fn subscribe_historical_bars(&mut self, contract: &contract::Contract, duration: HistoricalDataDuration,
+        bar_period: HistoricalDataBarSize, what_to_show: HistoricalDataType, use_rth: bool) -> AsyncResult<bars::BarSubscription> {
+        if !self.is_connected() {
+            return Err(Box::new(SocketError));
+        }
+        historical::validate_subscription(contract.sec_type.as_ref(), &duration, &bar_period, &what_to_show)?;
+        self.hist_pacer.pace().await;
+        let mut msg = Outgoing::ReqHistoricalData.encode();
+        let id = self.get_next_req_id();
+        msg.push_str(&id.encode());
+        msg.push_str(&contract.encode_for_hist_data());
+        msg.push('\0'); //end date time must be empty for keepUpToDate requests
+        msg.push_str(&bar_period.encode());
+        msg.push_str(&duration.encode());
+        msg.push_str(&use_rth.encode());
+        msg.push_str(&what_to_show.encode());
+        msg.push_str("2\0"); //epoch time stamps
+        msg.push_str(&true.encode()); //keepUpToDate
+        msg.push('\0'); //chart options
+        let guard = self.historical_data_guard(id);
+        match self.make_bar_subscription(id, msg).await? {
+            Response::BarSubscription(bars, updates_rx) => {
+                let tz = self.exchange_time_zone(contract).await;
+                Ok(bars::BarSubscription::new(bars.localize(&tz), tz, updates_rx, guard))
+            },
+            Response::TWSError(error) => {
+                guard.disarm();
+                Err(Box::new(error))
+            },
+            _ => Err(Box::new(ResponseError{}))
+        }
+    }
+    /// Returns the number of historical data requests sent in the last 10 minutes. IB allows at most 60, further
+    /// requests are delayed by the client.
+    pub fn historical_requests_in_window(&self) -> usize {
+        self.hist_pacer.requests_in_window()
+    }
     /// Requests historical price bar data adjusted for dividends and splits (stocks only).
+    /// Dropping the returned future before it completes cancels the request.
     pub async fn req_adj_historical_data(&mut self, contract: &contract::Contract, duration: HistoricalDataDuration, bar_period: HistoricalDataBarSize, use_rth: bool) -> AsyncResult<bars::BarSeries> {
         if !self.is_connected() {
             return Err(Box::new(SocketError));
         }
+        historical::validate_bars(contract.sec_type.as_ref(), &duration, &bar_period, None)?;
+        self.hist_pacer.pace().await;
         let mut msg = Outgoing::ReqHistoricalData.encode();
         let id = self.get_next_req_id();
         msg.push_str(&id.encode());
@@ -753,13 +1134,262 @@ impl IBClient
         msg.push_str(&duration.encode());
         msg.push_str(&use_rth.encode());
         msg.push_str("ADJUSTED_LAST\0");
-        msg.push_str("1\00\0\0");
+        msg.push_str("2\00\0\0"); //epoch time stamps, no keepUpToDate, no chart options
+        let guard = self.historical_data_guard(id);
+        let response = self.make_request(id, msg).await?;
+        guard.disarm();
+        match response {
+            Response::Bars(bars) => Ok(bars.localize(&self.exchange_time_zone(contract).await)),
+            Response::TWSError(error) => Err(Box::new(error)),
+            _ => Err(Box::new(ResponseError{}))
+        }
+    }
+    /// Requests the trading schedule of the contract for the given duration ending at `end`. The sessions are the exact
+    /// trading sessions of the exchange including holidays and early closes; with `use_rth` only regular trading hours are
+    /// returned. Requires server version 165 or newer.
+    pub async fn req_trading_schedule<Tz: TimeZone>(&mut self, contract: &contract::Contract, end: &DateTime<Tz>,
+        duration: HistoricalDataDuration, use_rth: bool) -> AsyncResult<calendar::TradingSchedule> {
+        if !self.is_connected() {
+            return Err(Box::new(SocketError));
+        }
+        if self.server_version < constants::MIN_SERVER_VER_HISTORICAL_SCHEDULE {
+            return Err(Box::new(ServerVersionError));
+        }
+        historical::validate_duration(&duration, &HistoricalDataBarSize::OneDay)?;
+        self.hist_pacer.pace().await;
+        let mut msg = Outgoing::ReqHistoricalData.encode();
+        let id = self.get_next_req_id();
+        msg.push_str(&id.encode());
+        msg.push_str(&contract.encode_for_hist_data());
+        msg.push_str(&end.with_timezone(&Utc).format("%Y%m%d-%H:%M:%S").to_string().encode());
+        msg.push_str(&HistoricalDataBarSize::OneDay.encode());
+        msg.push_str(&duration.encode());
+        msg.push_str(&use_rth.encode());
+        msg.push_str(&HistoricalDataType::Schedule.encode());
+        msg.push_str("2\0"); //epoch time stamps
+        msg.push_str(&false.encode()); //keepUpToDate
+        msg.push('\0'); //chart options
+        let guard = self.historical_data_guard(id);
+        let response = self.make_request(id, msg).await?;
+        guard.disarm();
+        match response {
+            Response::TradingSchedule(schedule) => Ok(schedule),
+            Response::TWSError(error) => Err(Box::new(error)),
+            _ => Err(Box::new(ResponseError{}))
+        }
+    }
+    /// Loads historical price bars between `start` and `end`, which may span more history than a single request allows.
+    /// The range is split into chunks of the longest duration IB allows for the bar size, which are requested
+    /// (paced) from the most recent to the oldest and stitched into one time-ordered series without duplicates.
+    /// Chunks that fail are reported in the returned `Backfill` together with the bars that were loaded.
+    /// If a `HistoricalStore` is set, only ranges missing in the store are requested and the merged bars are stored.
+    /// Errors reading the store are returned as `std::io::Error`, errors writing it are logged.
+    pub async fn backfill_bars<Tz: TimeZone>(&mut self, contract: &contract::Contract, start: &DateTime<Tz>, end: &DateTime<Tz>,
+        bar_size: HistoricalDataBarSize, what_to_show: HistoricalDataType, use_rth: bool) -> AsyncResult<historical::Backfill> {
+        Ok(self.backfill(contract, start, end, bar_size, what_to_show, use_rth).await?.0)
+    }
+    /// Runs a backfill and returns the error of the first failed chunk along with the result.
+    async fn backfill<Tz: TimeZone>(&mut self, contract: &contract::Contract, start: &DateTime<Tz>, end: &DateTime<Tz>,
+        bar_size: HistoricalDataBarSize, what_to_show: HistoricalDataType, use_rth: bool)
+        -> AsyncResult<(historical::Backfill, Option<Box<dyn Error>>)> {
+        if !self.is_connected() {
+            return Err(Box::new(SocketError));
+        }
+        historical::validate_backfill(contract.sec_type.as_ref())?;
+        let start = start.with_timezone(&Utc);
+        let end = end.with_timezone(&Utc);
+        let tz = self.exchange_time_zone(contract).await;
+        let key = match self.hist_store {
+            Some(_) => match contract.con_id() {
+                Some(con_id) => Some(con_id),
+                None => self.cached_contract_details(contract).await
+                    .and_then(|details| details.contract().and_then(|c| c.con_id()))
+            }.map(|con_id| historical::SeriesKey {con_id, bar_size, what_to_show, use_rth}),
+            None => None
+        };
+        let mut stored = match (&self.hist_store, &key) {
+            (Some(store), Some(key)) => store.load(key, &tz)?,
+            _ => historical::StoredBars::default()
+        };
+        let mut data = Vec::new();
+        let mut failed_chunks = Vec::new();
+        let mut first_error = None;
+        for (range_start, range_end) in stored.missing(&start, &end) {
+            for (chunk_start, chunk_end, duration) in historical::chunks(&range_start, &range_end, &bar_size) {
+                match self.fetch_historical_data(contract, &chunk_end, duration, bar_size, what_to_show, use_rth).await {
+                    Ok(series) => {
+                        data.extend(series.data);
+                        stored.cover(chunk_start, chunk_end);
+                    },
+                    Err(err) => match err.downcast_ref::<TWSError>() {
+                        Some(tws_error) if tws_error.is_no_data() => stored.cover(chunk_start, chunk_end),
+                        _ => {
+                            warn!("Backfill chunk from {} to {} failed: {}", chunk_start, chunk_end, err);
+                            failed_chunks.push(historical::FailedChunk {start: chunk_start, end: chunk_end, error: err.to_string()});
+                            first_error.get_or_insert(err);
+                        }
+                    }
+                }
+            }
+        }
+        //newly loaded bars take precedence over stored bars with the same time stamp
+        data.append(&mut stored.bars);
+        data.sort_by_key(|bar| bar.t_stamp);
+        data.dedup_by(|a, b| a.t_stamp == b.t_stamp);
+        if let (Some(store), Some(key)) = (&self.hist_store, &key) {
+            stored.bars = data.clone();
+            stored.invalidate_open_bar(&bar_size, &Utc::now());
+            if let Err(err) = store.save(key, &stored) {
+                warn!("Bars could not be stored: {}", err);
+            }
+        }
+        data.retain(|bar| bar.t_stamp >= start && bar.t_stamp < end);
+        Ok((historical::Backfill {
+            series: bars::BarSeries {
+                start_dt: Some(start.with_timezone(&tz)),
+                end_dt: Some(end.with_timezone(&tz)),
+                data
+            },
+            failed_chunks
+        }, first_error))
+    }
+    /// Requests historical tick data between `start` and `end`. `count` is the number of ticks per request and is
+    /// limited to 1000 by the TWS API; the request is repeated with a moving start time until the interval is covered.
+    pub async fn req_historical_ticks<Tz: TimeZone>(&mut self, contract: &contract::Contract, start: &DateTime<Tz>, end: &DateTime<Tz>,
+        count: usize, what_to_show: HistoricalTicksType, use_rth: bool) -> AsyncResult<ticks::HistoricalTicks> {
+        let end = end.with_timezone(&Utc);
+        let mut cursor = start.with_timezone(&Utc);
+        let page_size = count.clamp(1, constants::MAX_HISTORICAL_TICKS);
+        let mut ticks = ticks::HistoricalTicks::new(&what_to_show);
+        while cursor <= end {
+            let page = self.req_historical_ticks_page(contract, &cursor, page_size, &what_to_show, use_rth).await?;
+            let n_page = page.len();
+            let page_end = match page.last_time() {
+                Some(time) => time,
+                None => break
+            };
+            //the page starts at the cursor second, so ticks at that second were already received with the last page
+            let skip = ticks.count_at(&cursor);
+            ticks.append_page(page, skip, &end);
+            cursor = match ticks::next_page_start(&cursor, &page_end, n_page, page_size, &end) {
+                Some(next) => next,
+                None => break
+            };
+        }
+        Ok(ticks)
+    }
+
+    async fn req_historical_ticks_page(&mut self, contract: &contract::Contract, start: &DateTime<Utc>, count: usize,
+        what_to_show: &HistoricalTicksType, use_rth: bool) -> AsyncResult<ticks::HistoricalTicks> {
+        if !self.is_connected() {
+            return Err(Box::new(SocketError));
+        }
+        let mut msg = Outgoing::ReqHistoricalTicks.encode();
+        let id = self.get_next_req_id();
+        msg.push_str(&id.encode());
+        msg.push_str(&contract.encode_for_hist_data());
+        msg.push_str(&start.format("%Y%m%d-%H:%M:%S").to_string().encode());
+        msg.push('\0'); //end date time, only one of start and end may be set
+        msg.push_str(&count.encode());
+        msg.push_str(&what_to_show.encode());
+        msg.push_str(&use_rth.encode());
+        msg.push_str(&false.encode()); //ignore size, keep size-only bid/ask updates
+        msg.push('\0'); //misc options
         match self.make_request(id, msg).await? {
-            Response::Bars(bars) => Ok(bars),
+            Response::HistoricalTicks(ticks) => Ok(ticks),
+            Response::TWSError(error) => Err(Box::new(error)),
+            _ => Err(Box::new(ResponseError{}))
+        }
+    }
+    /// Requests the time stamp of the earliest available historical data for the contract.
+    pub async fn req_head_timestamp(&mut self, contract: &contract::Contract, what_to_show: HistoricalDataType, use_rth: bool) -> AsyncResult<DateTime<Utc>> {
+        if !self.is_connected() {
+            return Err(Box::new(SocketError));
+        }
+        let mut msg = Outgoing::ReqHeadTimestamp.encode();
+        let id = self.get_next_req_id();
+        msg.push_str(&id.encode());
+        msg.push_str(&contract.encode_for_hist_data());
+        msg.push_str(&use_rth.encode());
+        msg.push_str(&what_to_show.encode());
+        msg.push_str(&2i32.encode()); //format date as epoch
+        //the head time stamp request stays active on the server until cancelled, so the guard is not disarmed
+        let mut cancel_msg = Outgoing::CancelHeadTimestamp.encode();
+        cancel_msg.push_str(&id.encode());
+        let _guard = self.request_guard(id, cancel_msg);
+        match self.make_request(id, msg).await? {
+            Response::HeadTimestamp(time) => Ok(time),
+            Response::TWSError(error) => Err(Box::new(error)),
+            _ => Err(Box::new(ResponseError{}))
+        }
+    }
+    /// Requests a histogram of the traded size per price over the given period.
+    pub async fn req_histogram_data(&mut self, contract: &contract::Contract, use_rth: bool, period: HistogramPeriod) -> AsyncResult<Vec<bars::HistogramEntry>> {
+        if !self.is_connected() {
+            return Err(Box::new(SocketError));
+        }
+        let mut msg = Outgoing::ReqHistogramData.encode();
+        let id = self.get_next_req_id();
+        msg.push_str(&id.encode());
+        msg.push_str(&contract.encode_for_hist_data());
+        msg.push_str(&use_rth.encode());
+        msg.push_str(&period.encode());
+        let mut cancel_msg = Outgoing::CancelHistogramData.encode();
+        cancel_msg.push_str(&id.encode());
+        let guard = self.request_guard(id, cancel_msg);
+        let response = self.make_request(id, msg).await?;
+        guard.disarm();
+        match response {
+            Response::Histogram(data) => Ok(data),
             Response::TWSError(error) => Err(Box::new(error)),
             _ => Err(Box::new(ResponseError{}))
         }
     }
+    /// Requests the market rule with the given ID, which defines valid price increments. Market rules are
+    /// cached by the client and only requested once.
+    /// Market rule requests carry no request ID, so errors cannot be attributed to them. The request fails if the
+    /// TWS does not respond within 10 seconds, e.g. for an unknown rule ID.
+    pub async fn req_market_rule(&mut self, id: i32) -> AsyncResult<market_rule::MarketRule> {
+        if let Some(rule) = self.market_rules.get(&id) {
+            return Ok(rule.clone());
+        }
+        if !self.is_connected() {
+            return Err(Box::new(SocketError));
+        }
+        let mut msg = Outgoing::ReqMarketRule.encode();
+        msg.push_str(&id.encode());
+        let (resp_tx, resp_rx) = oneshot::channel();
+        let response = self.submit_request(Request::MarketRule{id, sender: resp_tx}, resp_rx, msg);
+        let response = match time::timeout(MARKET_RULE_TIMEOUT, response).await {
+            Ok(response) => response?,
+            Err(elapsed) => {
+                let _ = self.req_tx.send(Request::CancelMarketRule(id));
+                return Err(Box::new(elapsed));
+            }
+        };
+        match response {
+            Response::MarketRule(rule) => {
+                self.market_rules.insert(id, rule.clone());
+                Ok(rule)
+            },
+            _ => Err(Box::new(ResponseError{}))
+        }
+    }
+    /// Returns the market rule for the contract on the given exchange. If the contract details do not list
+    /// a market rule for the exchange, a rule based on the minimum tick of the contract is returned.
+    pub async fn req_price_rule(&mut self, details: &contract::ContractDetails, exchange: &str) -> AsyncResult<market_rule::MarketRule> {
+        match details.market_rule_id(exchange) {
+            Some(id) => self.req_market_rule(id).await,
+            None => match details.min_tick() {
+                Some(min_tick) => Ok(market_rule::MarketRule::from_min_tick(min_tick)),
+                None => Err(Box::new(PriceRuleError))
+            }
+        }
+    }
+    /// Rounds a limit or auxiliary price to the nearest valid price increment of the contract on the given exchange.
+    pub async fn round_price(&mut self, details: &contract::ContractDetails, exchange: &str, price: Decimal) -> AsyncResult<Decimal> {
+        Ok(self.req_price_rule(details, exchange).await?.round(price))
+    }
     /// Configures market data type as delayed data (no real-time subscription required).
     pub async fn set_mkt_data_delayed(&mut self) -> AsyncResult<()> {
         if !self.is_connected() {
@@ -785,8 +1415,19 @@ impl IBClient
         Ok(())
     }
 
+    /// Requests the option parameters of an underlying with con_id and returns the first entry, e.g. of the only
+    /// trading class on the given exchange. Use `req_all_options_metadata` to get all exchanges and trading classes.
     pub async fn req_options_metadata(&mut self, contract: &contract::Contract,
                                       exchange: Option<&str>) ->AsyncResult<opt_params::OptParams> {
+        match self.req_all_options_metadata(contract, exchange).await?.into_iter().next() {
+            Some(params) => Ok(params),
+            None => Err(Box::new(ResponseError{}))
+        }
+    }
+    /// Requests the option parameters of an underlying with con_id: one entry per exchange and trading class.
+    /// Pass `None` as exchange to get the entries of all exchanges.
+    pub async fn req_all_options_metadata(&mut self, contract: &contract::Contract,
+                                      exchange: Option<&str>) ->AsyncResult<Vec<opt_params::OptParams>> {
         if !self.is_connected() {
             return Err(Box::new(SocketError));
         }
@@ -807,7 +1448,45 @@ impl IBClient
         }
         else {Err(Box::new(ResponseError{}))}
     }
-
+    /// Requests the option chain of an underlying, which is qualified first. The chain holds the option parameters
+    /// of all exchanges and trading classes; option contracts are only created and qualified when they are needed,
+    /// e.g. with `qualify_option_chain` after filtering the chain.
+    pub async fn req_option_chain(&mut self, underlying: &contract::Contract) -> AsyncResult<opt_params::OptionChain> {
+        let mut underlying = underlying.clone();
+        self.qualify(&mut underlying).await?;
+        let params = self.req_all_options_metadata(&underlying, None).await?;
+        Ok(opt_params::OptionChain::new(underlying, params))
+    }
+    /// Qualifies the legs of an option strategy and returns the BAG contract, e.g. for `Order::limit`.
+    pub async fn qualify_strategy(&mut self, strategy: &strategy::OptionStrategy) -> AsyncResult<contract::Contract> {
+        let legs = self.qualify_many(&strategy.leg_contracts()).await?;
+        strategy.combo(&legs).ok_or_else(|| Box::new(UnknownContractError) as Box<dyn Error>)
+    }
+    /// Requests all listed futures contracts of a symbol on an exchange, e.g. `ES` on `CME`, sorted by expiry.
+    pub async fn req_futures_chain(&mut self, symbol: &str, exchange: &str, currency: &str) -> AsyncResult<futures_chain::FuturesChain> {
+        //a future without contract month matches all listed contracts
+        let contract = contract::Contract {
+            symbol: Some(symbol.to_string()),
+            sec_type: Some(SecType::Future),
+            exchange: Some(exchange.to_string()),
+            currency: Some(currency.to_string()),
+            ..Default::default()
+        };
+        let details = self.req_contract_details(&contract).await?;
+        Ok(futures_chain::FuturesChain::from_details(details))
+    }
+    /// Qualifies the option contracts of the chain. Combinations of expiry and strike that are not listed are skipped.
+    pub async fn qualify_option_chain(&mut self, chain: &opt_params::OptionChain) -> AsyncResult<Vec<contract::Contract>> {
+        let mut qualified = Vec::new();
+        for mut contract in chain.contracts() {
+            match self.qualify(&mut contract).await {
+                Ok(()) => qualified.push(contract),
+                Err(err) if err.is::<UnknownContractError>() || err.downcast_ref::<TWSError>().is_some_and(|err| err.code == Some(200)) => continue,
+                Err(err) => return Err(err)
+            }
+        }
+        Ok(qualified)
+    }
 }
 /// The `IBClient` shuts down all detached tasks used to manage the socket connection on `Drop`.
 impl Drop for IBClient {
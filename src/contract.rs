@@ -2,10 +2,100 @@ use crate::enums;
 use rust_decimal::prelude::*;
 use crate::enums::*;
 use crate::utils::ib_message::Encodable;
-use chrono::{DateTime,NaiveDateTime,Utc,TimeZone};
+use std::error::Error;
+use chrono::{DateTime, NaiveDate};
 use chrono_tz::Tz;
-use chrono_tz::{UTC,US};
+use std::{fmt, io};
+#[cfg(feature = "serde")]
+use std::fs;
+use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+#[cfg(feature = "serde")]
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use log::warn;
+use crate::calendar;
+
+#[derive(Debug)]
+struct InvalidContractError(String);
+
+impl Error for InvalidContractError {}
+
+impl fmt::Display for InvalidContractError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid contract: {}", self.0) // user-facing output
+    }
+}
+
+fn invalid_contract(reason: String) -> Box<dyn Error> {
+    Box::new(InvalidContractError(reason))
+}
+
+fn required(name: &str, val: &str) -> Result<Option<String>, Box<dyn Error>> {
+    let val = val.trim();
+    if val.is_empty() {
+        return Err(invalid_contract(format!("{} is required", name)));
+    }
+    Ok(Some(val.to_string()))
+}
+
+/// Validates an expiry or contract month, `yyyymm` or `yyyymmdd`.
+fn contract_month(val: &str) -> Result<Option<String>, Box<dyn Error>> {
+    let val = val.trim();
+    if !matches!(val.len(), 6 | 8) || !val.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid_contract(format!("expiry or contract month {} is not yyyymm or yyyymmdd", val)));
+    }
+    Ok(Some(val.to_string()))
+}
+
+fn strike(val: Decimal) -> Result<Option<Decimal>, Box<dyn Error>> {
+    if val <= Decimal::ZERO {
+        return Err(invalid_contract(format!("strike {} is not positive", val)));
+    }
+    Ok(Some(val))
+}
+
+fn right(val: OptionRight) -> Result<Option<OptionRight>, Box<dyn Error>> {
+    if let OptionRight::Undefined = val {
+        return Err(invalid_contract("option right is required".to_string()));
+    }
+    Ok(Some(val))
+}
+
+fn currency(val: &str) -> Result<Option<String>, Box<dyn Error>> {
+    let val = val.trim();
+    if val.len() != 3 || !val.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(invalid_contract(format!("currency {} is not a 3 letter code", val)));
+    }
+    Ok(Some(val.to_ascii_uppercase()))
+}
+
+/// Validates a CUSIP (9 characters), ISIN or FIGI (12 characters).
+fn sec_id(sec_id_type: &SecIdType, val: &str) -> Result<Option<String>, Box<dyn Error>> {
+    let val = val.trim().to_ascii_uppercase();
+    let len = match sec_id_type {
+        SecIdType::Cusip => 9,
+        SecIdType::Isin | SecIdType::Figi => 12
+    };
+    if val.len() != len || !val.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(invalid_contract(format!("{:?} {} is not {} alphanumeric characters", sec_id_type, val, len)));
+    }
+    Ok(Some(val))
+}
+
+/// Splits a comma separated list as sent by the TWS, skipping empty entries.
+fn split_list(val: &Option<String>) -> Vec<&str> {
+    val.as_deref().map_or_else(Vec::new, |val| val.split(',').map(str::trim).filter(|entry| !entry.is_empty()).collect())
+}
+
+fn parse_decimal(val: &Option<String>) -> Option<Decimal> {
+    Decimal::from_str(val.as_ref()?.trim()).ok()
+}
+
 #[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ComboLeg {
     pub(crate) con_id: Option<i32>,
     pub(crate) ratio: Option<i32>,
@@ -23,8 +113,25 @@ impl ComboLeg {
             con_id: Some(con_id), ratio: Some(ratio), action: Some(action), exchange: Some(exchange.to_string()), open_close: None, shortsale_slot: None, designated_location: None, exempt_code: None
         }
     }
+    /// Returns the con_id of the leg contract.
+    pub fn con_id(&self) -> Option<i32> {
+        self.con_id
+    }
+    /// Returns the number of leg contracts per unit of the combo.
+    pub fn ratio(&self) -> Option<i32> {
+        self.ratio
+    }
+    /// Returns whether the leg is bought or sold when the combo is bought.
+    pub fn action(&self) -> Option<&enums::ComboAction> {
+        self.action.as_ref()
+    }
+    /// Returns the exchange of the leg.
+    pub fn exchange(&self) -> Option<&str> {
+        self.exchange.as_deref()
+    }
 }
 #[derive(Default,Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct DeltaNeutralContract {
     pub con_id: Option<i32>,
     pub delta: Option<Decimal>,
@@ -32,6 +139,7 @@ pub(crate) struct DeltaNeutralContract {
 }
 
 #[derive(Default,Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Contract {
     pub(crate) con_id: Option<i32>,
     pub(crate) symbol: Option<String>,
@@ -171,6 +279,144 @@ impl Contract {
     pub fn stock_us_smart(symbol: &str) -> Self {
         Self::stock(symbol, "SMART", "USD")
     }
+    /// Creates an option contract. The expiry is `yyyymmdd`, or the contract month `yyyymm`.
+    pub fn option(symbol: &str, expiry: &str, strike: Decimal, right: OptionRight, exchange: &str, currency: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(Contract {
+            symbol: required("symbol", symbol)?,
+            sec_type: Some(SecType::Option),
+            last_trade_date_or_contract_month: contract_month(expiry)?,
+            strike: self::strike(strike)?,
+            right: self::right(right)?,
+            exchange: required("exchange", exchange)?,
+            currency: self::currency(currency)?,
+            ..Default::default()
+        })
+    }
+    /// Creates an option contract on a US underlying with SMART routing and the standard multiplier of 100.
+    pub fn option_us_smart(symbol: &str, expiry: &str, strike: Decimal, right: OptionRight) -> Result<Self, Box<dyn Error>> {
+        Ok(Self::option(symbol, expiry, strike, right, "SMART", "USD")?.with_multiplier("100"))
+    }
+    /// Creates a futures contract for the contract month `yyyymm` (or the last trading day `yyyymmdd`).
+    pub fn future(symbol: &str, contract_month: &str, exchange: &str, currency: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(Contract {
+            symbol: required("symbol", symbol)?,
+            sec_type: Some(SecType::Future),
+            last_trade_date_or_contract_month: self::contract_month(contract_month)?,
+            exchange: required("exchange", exchange)?,
+            currency: self::currency(currency)?,
+            ..Default::default()
+        })
+    }
+    /// Creates a continuous futures contract, which stitches the front month contracts for historical data requests.
+    pub fn continuous_future(symbol: &str, exchange: &str, currency: &str) -> Result<Self, Box<dyn Error>> {
+        Self::simple(SecType::ContinuousFuture, symbol, exchange, currency)
+    }
+    /// Creates an option on a future for the contract month `yyyymm` (or the expiry `yyyymmdd`).
+    pub fn future_option(symbol: &str, contract_month: &str, strike: Decimal, right: OptionRight, exchange: &str, currency: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(Contract {
+            sec_type: Some(SecType::OptionOnFuture),
+            ..Self::option(symbol, contract_month, strike, right, exchange, currency)?
+        })
+    }
+    /// Creates a warrant contract.
+    pub fn warrant(symbol: &str, expiry: &str, strike: Decimal, right: OptionRight, exchange: &str, currency: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(Contract {
+            sec_type: Some(SecType::Warrant),
+            ..Self::option(symbol, expiry, strike, right, exchange, currency)?
+        })
+    }
+    /// Creates a currency pair traded on IDEALPRO, e.g. `EUR.USD` or `EURUSD`.
+    pub fn forex(pair: &str) -> Result<Self, Box<dyn Error>> {
+        let pair = pair.trim().replace(&['.', '/'][..], "");
+        if pair.len() != 6 || !pair.is_ascii() {
+            return Err(invalid_contract(format!("currency pair {} is not of the form EUR.USD", pair)));
+        }
+        Ok(Contract {
+            symbol: currency(&pair[..3])?,
+            sec_type: Some(SecType::Forex),
+            exchange: Some("IDEALPRO".to_string()),
+            currency: currency(&pair[3..])?,
+            ..Default::default()
+        })
+    }
+    /// Creates an index contract.
+    pub fn index(symbol: &str, exchange: &str, currency: &str) -> Result<Self, Box<dyn Error>> {
+        Self::simple(SecType::Index, symbol, exchange, currency)
+    }
+    /// Creates a contract for difference, usually with SMART routing.
+    pub fn cfd(symbol: &str, exchange: &str, currency: &str) -> Result<Self, Box<dyn Error>> {
+        Self::simple(SecType::Cfd, symbol, exchange, currency)
+    }
+    /// Creates a commodity contract, e.g. `XAUUSD` on SMART.
+    pub fn commodity(symbol: &str, exchange: &str, currency: &str) -> Result<Self, Box<dyn Error>> {
+        Self::simple(SecType::Commodity, symbol, exchange, currency)
+    }
+    /// Creates a mutual fund contract.
+    pub fn mutual_fund(symbol: &str, exchange: &str, currency: &str) -> Result<Self, Box<dyn Error>> {
+        Self::simple(SecType::MutualFund, symbol, exchange, currency)
+    }
+    /// Creates a crypto currency contract traded on PAXOS, e.g. `BTC` in `USD`.
+    pub fn crypto(symbol: &str, currency: &str) -> Result<Self, Box<dyn Error>> {
+        Self::simple(SecType::Crypto, symbol, "PAXOS", currency)
+    }
+    /// Creates a bond contract identified by its CUSIP (9 characters), ISIN or FIGI (12 characters).
+    pub fn bond(sec_id_type: SecIdType, sec_id: &str, exchange: &str, currency: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(Contract {
+            sec_type: Some(SecType::Bond),
+            sec_id: self::sec_id(&sec_id_type, sec_id)?,
+            sec_id_type: Some(sec_id_type),
+            exchange: required("exchange", exchange)?,
+            currency: self::currency(currency)?,
+            ..Default::default()
+        })
+    }
+    /// Creates a contract that is only identified by its security ID, e.g. for `IBClient::lookup_by_sec_id`.
+    pub fn security(sec_id_type: SecIdType, sec_id: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(Contract {
+            sec_id: self::sec_id(&sec_id_type, sec_id)?,
+            sec_id_type: Some(sec_id_type),
+            ..Default::default()
+        })
+    }
+    fn simple(sec_type: SecType, symbol: &str, exchange: &str, currency: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(Contract {
+            symbol: required("symbol", symbol)?,
+            sec_type: Some(sec_type),
+            exchange: required("exchange", exchange)?,
+            currency: self::currency(currency)?,
+            ..Default::default()
+        })
+    }
+    /// Sets the contract ID, which identifies the contract without further fields.
+    pub fn with_con_id(mut self, con_id: i32) -> Self {
+        self.con_id = Some(con_id);
+        self
+    }
+    /// Sets the contract multiplier, e.g. 100 for US equity options.
+    pub fn with_multiplier(mut self, multiplier: &str) -> Self {
+        self.multiplier = Some(multiplier.to_string());
+        self
+    }
+    /// Sets the trading class, e.g. `SPXW` for the weekly SPX options.
+    pub fn with_trading_class(mut self, trading_class: &str) -> Self {
+        self.trading_class = Some(trading_class.to_string());
+        self
+    }
+    /// Sets the primary exchange to resolve ambiguous SMART routed contracts.
+    pub fn with_primary_exchange(mut self, primary_exchange: &str) -> Self {
+        self.primary_exchange = Some(primary_exchange.to_string());
+        self
+    }
+    /// Sets the local symbol, e.g. `ESH4` for a future.
+    pub fn with_local_symbol(mut self, local_symbol: &str) -> Self {
+        self.local_symbol = Some(local_symbol.to_string());
+        self
+    }
+    /// Includes expired contracts, e.g. to request historical data of expired futures.
+    pub fn with_expired(mut self) -> Self {
+        self.include_expired = Some(true);
+        self
+    }
     pub fn combo(symbol: &str, exchange: &str, currency: &str) -> Self {
         Contract {
             symbol: Some(symbol.to_string()),
@@ -213,15 +459,350 @@ impl Contract {
         }
         ret
     }
-    pub fn symbol(&self) -> &Option<String> {
-        &self.symbol
+    /// Fills the con_id and all fields that are not set from a qualified contract.
+    pub(crate) fn fill_from(&mut self, qualified: &Contract) {
+        fn fill<T: Clone>(field: &mut Option<T>, val: &Option<T>) {
+            if field.is_none() {
+                *field = val.clone();
+            }
+        }
+        self.con_id = qualified.con_id;
+        fill(&mut self.symbol, &qualified.symbol);
+        fill(&mut self.sec_type, &qualified.sec_type);
+        fill(&mut self.last_trade_date_or_contract_month, &qualified.last_trade_date_or_contract_month);
+        fill(&mut self.strike, &qualified.strike);
+        fill(&mut self.right, &qualified.right);
+        fill(&mut self.multiplier, &qualified.multiplier);
+        fill(&mut self.exchange, &qualified.exchange);
+        fill(&mut self.currency, &qualified.currency);
+        fill(&mut self.local_symbol, &qualified.local_symbol);
+        fill(&mut self.primary_exchange, &qualified.primary_exchange);
+        fill(&mut self.trading_class, &qualified.trading_class);
+    }
+    /// Returns the symbol, e.g. `AAPL` or the underlying of a derivative.
+    pub fn symbol(&self) -> Option<&str> {
+        self.symbol.as_deref()
     }
     pub fn con_id(&self) -> Option<i32> {
         self.con_id
     }
+    /// Returns the security type.
+    pub fn sec_type(&self) -> Option<&enums::SecType> {
+        self.sec_type.as_ref()
+    }
+    /// Returns the last trade date `yyyymmdd` or contract month `yyyymm` of derivatives.
+    pub fn last_trade_date_or_contract_month(&self) -> Option<&str> {
+        self.last_trade_date_or_contract_month.as_deref()
+    }
+    /// Returns the strike of options and warrants.
+    pub fn strike(&self) -> Option<Decimal> {
+        self.strike
+    }
+    /// Returns the right of options and warrants.
+    pub fn right(&self) -> Option<&enums::OptionRight> {
+        self.right.as_ref()
+    }
+    /// Returns the contract multiplier, `None` if it is not set or not a number.
+    pub fn multiplier(&self) -> Option<Decimal> {
+        parse_decimal(&self.multiplier)
+    }
+    /// Returns the exchange the contract is routed to, e.g. SMART.
+    pub fn exchange(&self) -> Option<&str> {
+        self.exchange.as_deref()
+    }
+    /// Returns the primary listing exchange.
+    pub fn primary_exchange(&self) -> Option<&str> {
+        self.primary_exchange.as_deref()
+    }
+    /// Returns the currency.
+    pub fn currency(&self) -> Option<&str> {
+        self.currency.as_deref()
+    }
+    /// Returns the symbol on the exchange, e.g. `ESZ3` for a future.
+    pub fn local_symbol(&self) -> Option<&str> {
+        self.local_symbol.as_deref()
+    }
+    /// Returns the trading class, e.g. `SPXW` for the weekly SPX options.
+    pub fn trading_class(&self) -> Option<&str> {
+        self.trading_class.as_deref()
+    }
+    /// Returns true if expired contracts are included in requests.
+    pub fn include_expired(&self) -> bool {
+        self.include_expired.unwrap_or(false)
+    }
+    /// Returns the type of the security ID, e.g. ISIN.
+    pub fn sec_id_type(&self) -> Option<&enums::SecIdType> {
+        self.sec_id_type.as_ref()
+    }
+    /// Returns the security ID.
+    pub fn sec_id(&self) -> Option<&str> {
+        self.sec_id.as_deref()
+    }
+    /// Returns the description of the combo legs.
+    pub fn combo_legs_description(&self) -> Option<&str> {
+        self.combo_legs_description.as_deref()
+    }
+    /// Returns the legs of a combo (BAG) contract.
+    pub fn combo_legs(&self) -> &[ComboLeg] {
+        self.combo_legs.as_deref().unwrap_or_default()
+    }
+}
+
+/// Returns the code of an enum as sent to the TWS, e.g. `STK`.
+fn code<T: Encodable>(val: &T) -> String {
+    val.encode().trim_end_matches('\0').to_string()
+}
+
+/// A string field as compared by contract identity: trimmed and empty if blank.
+fn field(val: &Option<String>) -> Option<&str> {
+    val.as_deref().map(str::trim).filter(|val| !val.is_empty())
 }
 
-#[derive(Default,Debug)]
+fn upper(val: &str) -> impl Iterator<Item = u8> + '_ {
+    val.bytes().map(|byte| byte.to_ascii_uppercase())
+}
+
+fn eq_field(a: &Option<String>, b: &Option<String>) -> bool {
+    match (field(a), field(b)) {
+        (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+        (a, b) => a.is_none() && b.is_none()
+    }
+}
+
+fn cmp_field(a: &Option<String>, b: &Option<String>) -> Ordering {
+    match (field(a), field(b)) {
+        (Some(a), Some(b)) => upper(a).cmp(upper(b)),
+        (a, b) => a.is_some().cmp(&b.is_some())
+    }
+}
+
+fn hash_field<H: Hasher>(val: &Option<String>, state: &mut H) {
+    match field(val) {
+        Some(val) => {
+            state.write_u8(1);
+            upper(val).for_each(|byte| state.write_u8(byte));
+            state.write_u8(0xff);
+        },
+        None => state.write_u8(0)
+    }
+}
+
+/// The security type of a contract identifier without an explicit type: options have a strike, futures only an expiry.
+fn implied_sec_type(expiry: bool, strike: bool) -> SecType {
+    match (expiry, strike) {
+        (_, true) => SecType::Option,
+        (true, false) => SecType::Future,
+        _ => SecType::Stock
+    }
+}
+
+/// The exchange of a contract identifier without an explicit exchange.
+fn default_exchange(sec_type: &SecType) -> &'static str {
+    match sec_type {
+        SecType::Forex => "IDEALPRO",
+        SecType::Crypto => "PAXOS",
+        _ => "SMART"
+    }
+}
+
+impl Contract {
+    fn qualified_id(&self) -> Option<i32> {
+        self.con_id.filter(|con_id| *con_id > 0)
+    }
+
+    fn defined_right(&self) -> Option<&OptionRight> {
+        self.right.as_ref().filter(|right| !matches!(right, OptionRight::Undefined))
+    }
+
+    fn leg_keys(&self) -> impl Iterator<Item = (Option<i32>, Option<i32>, Option<&enums::ComboAction>)> + '_ {
+        self.combo_legs().iter().map(|leg| (leg.con_id, leg.ratio, leg.action.as_ref()))
+    }
+}
+
+/// Contracts with a con_id are equal if their con_ids are equal. Contracts without a con_id are equal if symbol,
+/// security type, expiry, strike, right, multiplier, exchange, currency, security ID and combo legs are equal,
+/// ignoring surrounding whitespace and upper and lower case. A qualified contract never equals an unqualified one,
+/// so contracts should be qualified before they are used as keys.
+impl PartialEq for Contract {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.qualified_id(), other.qualified_id()) {
+            (Some(con_id), Some(other_con_id)) => con_id == other_con_id,
+            (None, None) => eq_field(&self.symbol, &other.symbol)
+                && self.sec_type == other.sec_type
+                && eq_field(&self.last_trade_date_or_contract_month, &other.last_trade_date_or_contract_month)
+                && self.strike == other.strike
+                && self.defined_right() == other.defined_right()
+                && self.multiplier() == other.multiplier()
+                && eq_field(&self.exchange, &other.exchange)
+                && eq_field(&self.currency, &other.currency)
+                && eq_field(&self.sec_id, &other.sec_id)
+                && self.leg_keys().eq(other.leg_keys()),
+            _ => false
+        }
+    }
+}
+
+impl Eq for Contract {}
+
+impl Hash for Contract {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        if let Some(con_id) = self.qualified_id() {
+            state.write_u8(0);
+            con_id.hash(state);
+            return;
+        }
+        state.write_u8(1);
+        hash_field(&self.symbol, state);
+        self.sec_type.hash(state);
+        hash_field(&self.last_trade_date_or_contract_month, state);
+        self.strike.map(|strike| strike.normalize()).hash(state);
+        self.defined_right().hash(state);
+        self.multiplier().map(|multiplier| multiplier.normalize()).hash(state);
+        hash_field(&self.exchange, state);
+        hash_field(&self.currency, state);
+        hash_field(&self.sec_id, state);
+        state.write_usize(self.combo_legs().len());
+        self.leg_keys().for_each(|leg| leg.hash(state));
+    }
+}
+
+/// Qualified contracts are ordered by con_id and before all unqualified contracts, which are ordered field by field
+/// in the order listed for equality.
+impl Ord for Contract {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.qualified_id(), other.qualified_id()) {
+            (Some(con_id), Some(other_con_id)) => con_id.cmp(&other_con_id),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => cmp_field(&self.symbol, &other.symbol)
+                .then_with(|| self.sec_type.cmp(&other.sec_type))
+                .then_with(|| cmp_field(&self.last_trade_date_or_contract_month, &other.last_trade_date_or_contract_month))
+                .then_with(|| self.strike.cmp(&other.strike))
+                .then_with(|| self.defined_right().cmp(&other.defined_right()))
+                .then_with(|| self.multiplier().cmp(&other.multiplier()))
+                .then_with(|| cmp_field(&self.exchange, &other.exchange))
+                .then_with(|| cmp_field(&self.currency, &other.currency))
+                .then_with(|| cmp_field(&self.sec_id, &other.sec_id))
+                .then_with(|| self.leg_keys().cmp(other.leg_keys()))
+        }
+    }
+}
+
+impl PartialOrd for Contract {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Formats the canonical identifier of the contract: the symbol, the expiry, the strike and right, e.g.
+/// `AAPL 20250117 150 C`, followed by the security type if it is not implied (stock without expiry, future with an
+/// expiry, option with a strike), `@` and the exchange if it is not the default (SMART, IDEALPRO for forex, PAXOS
+/// for crypto) and the currency if it is not USD, e.g. `ES 202312 @CME` or `EUR CASH JPY`. Bonds are identified by
+/// their security ID, e.g. `CUSIP:912828C57 BOND`. The identifier does not include the con_id, multiplier, trading
+/// class and combo legs.
+impl fmt::Display for Contract {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut tokens: Vec<String> = Vec::new();
+        match (&self.symbol, &self.sec_id_type, &self.sec_id) {
+            (Some(symbol), _, _) => tokens.push(symbol.clone()),
+            (None, Some(id_type), Some(id)) => tokens.push(format!("{}:{}", code(id_type), id)),
+            _ => ()
+        }
+        let expiry = self.last_trade_date_or_contract_month.as_ref();
+        let right = match self.right {
+            Some(OptionRight::Call) => Some("C"),
+            Some(OptionRight::Put) => Some("P"),
+            _ => None
+        };
+        let option = self.strike.zip(right).filter(|_| expiry.is_some());
+        tokens.extend(expiry.cloned());
+        if let Some((strike, right)) = option {
+            tokens.push(strike.normalize().to_string());
+            tokens.push(right.to_string());
+        }
+        let implied = implied_sec_type(expiry.is_some(), option.is_some());
+        if let Some(sec_type) = self.sec_type.as_ref().filter(|sec_type| **sec_type != implied) {
+            tokens.push(code(sec_type));
+        }
+        if let Some(exchange) = self.exchange.as_ref()
+            .filter(|exchange| !exchange.eq_ignore_ascii_case(default_exchange(self.sec_type.as_ref().unwrap_or(&implied)))) {
+            tokens.push(format!("@{}", exchange));
+        }
+        if let Some(currency) = self.currency.as_ref().filter(|currency| !currency.eq_ignore_ascii_case("USD")) {
+            tokens.push(currency.clone());
+        }
+        write!(f, "{}", tokens.join(" "))
+    }
+}
+
+fn is_contract_month(val: &str) -> bool {
+    matches!(val.len(), 6 | 8) && val.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Parses a canonical contract identifier as formatted by `Display`, e.g. `AAPL 20250117 150 C` or `ES 202312 @CME`.
+/// Omitted exchanges and currencies are set to their defaults. Symbols may contain spaces, e.g. `BRK B`.
+impl FromStr for Contract {
+    type Err = Box<dyn Error>;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens: Vec<&str> = s.split_whitespace().collect();
+        //the optional parts are parsed from the end, the remaining tokens form the symbol
+        let mut currency = "USD";
+        if let Some(last) = tokens.last().filter(|last| tokens.len() > 1 && last.len() == 3
+            && last.chars().all(|c| c.is_ascii_uppercase()) && SecType::from_str(last).is_err()) {
+            currency = last;
+            tokens.pop();
+        }
+        let mut exchange = None;
+        if let Some(last) = tokens.last().filter(|last| tokens.len() > 1 && last.starts_with('@')) {
+            exchange = Some(&last[1..]);
+            tokens.pop();
+        }
+        let mut sec_type = None;
+        if let Some(last) = tokens.last().filter(|_| tokens.len() > 1) {
+            if let Ok(val) = SecType::from_str(last) {
+                sec_type = Some(val);
+                tokens.pop();
+            }
+        }
+        let mut option = None;
+        let n = tokens.len();
+        if n > 3 && is_contract_month(tokens[n - 3]) {
+            if let (Ok(strike), Ok(right @ (OptionRight::Call | OptionRight::Put))) = (Decimal::from_str(tokens[n - 2]), OptionRight::from_str(tokens[n - 1])) {
+                option = Some((strike, right));
+                tokens.truncate(n - 2);
+            }
+        }
+        let mut expiry = None;
+        if let Some(last) = tokens.last().filter(|last| tokens.len() > 1 && is_contract_month(last)) {
+            expiry = Some(*last);
+            tokens.pop();
+        }
+        let symbol = tokens.join(" ");
+        let sec_type = sec_type.unwrap_or_else(|| implied_sec_type(expiry.is_some(), option.is_some()));
+        let exchange = exchange.unwrap_or_else(|| default_exchange(&sec_type));
+        if let (SecType::Bond, Some((id_type, id))) = (&sec_type, symbol.split_once(':')) {
+            let id_type = SecIdType::from_str(id_type).map_err(|_| invalid_contract(format!("unknown security ID type {}", id_type)))?;
+            return Contract::bond(id_type, id, exchange, currency);
+        }
+        let (strike, right) = match option {
+            Some((strike, right)) => (self::strike(strike)?, self::right(right)?),
+            None => (None, None)
+        };
+        Ok(Contract {
+            symbol: required("symbol", &symbol)?,
+            sec_type: Some(sec_type),
+            last_trade_date_or_contract_month: expiry.map(contract_month).transpose()?.flatten(),
+            strike,
+            right,
+            exchange: required("exchange", exchange)?,
+            currency: self::currency(currency)?,
+            ..Default::default()
+        })
+    }
+}
+
+#[derive(Default,Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ContractDetails {
     pub(crate) contract: Option<Contract>,
     pub(crate) market_name: Option<String>,
@@ -256,53 +837,321 @@ pub struct ContractDetails {
     pub(crate) coupon_type: Option<String>,
     pub(crate) callable: Option<bool>,
     pub(crate) putable: Option<bool>,
-    pub(crate) coupon: Option<bool>,
+    pub(crate) coupon: Option<Decimal>,
     pub(crate) convertible: Option<bool>,
-    pub(crate) maturity: Option<bool>,
-    pub(crate) issue_date: Option<bool>,
-    pub(crate) next_option_date: Option<bool>,
-    pub(crate) next_option_type: Option<bool>,
+    pub(crate) maturity: Option<NaiveDate>,
+    pub(crate) issue_date: Option<NaiveDate>,
+    pub(crate) next_option_date: Option<NaiveDate>,
+    pub(crate) next_option_type: Option<String>,
+    pub(crate) next_option_partial: Option<bool>,
     pub(crate) notes: Option<String>,
     pub(crate) min_size: Option<String>,
     pub(crate) size_increment: Option<String>,
     pub(crate) suggested_size_increment: Option<String>
 }
-///Returns the liquid hours (i.e. trading calendar) of the contract.
 impl ContractDetails {
+    fn sessions(&self, hours: &Option<String>, name: &str) -> Result<Vec<calendar::TradingSession>, Box<dyn Error>> {
+        let tz = self.time_zone().ok_or_else(|| format!("Unknown time zone {:?} of contract details.", self.timezone_id))?;
+        let hours = hours.as_ref().ok_or_else(|| format!("No {} in contract details.", name))?;
+        calendar::parse_trading_hours(hours, &tz)
+    }
+    /// Returns the trading sessions including extended hours in the time zone of the exchange. Closed days are
+    /// skipped, days with several segments yield one session per segment.
+    pub fn trading_sessions(&self) -> Result<Vec<calendar::TradingSession>, Box<dyn Error>> {
+        self.sessions(&self.trading_hours, "trading hours")
+    }
+    /// Returns the liquid (regular) trading sessions in the time zone of the exchange.
+    pub fn liquid_sessions(&self) -> Result<Vec<calendar::TradingSession>, Box<dyn Error>> {
+        self.sessions(&self.liquid_hours, "liquid hours")
+    }
+    /// Returns the trading calendar with the liquid and extended sessions of the contract.
+    pub fn trading_calendar(&self) -> Result<calendar::TradingCalendar, Box<dyn Error>> {
+        let tz = self.time_zone().ok_or_else(|| format!("Unknown time zone {:?} of contract details.", self.timezone_id))?;
+        Ok(calendar::TradingCalendar::new(tz, self.liquid_sessions()?, self.trading_sessions()?))
+    }
+    /// Returns the open and close of the trading sessions including extended hours, `None` if they cannot be parsed.
+    pub fn trading_hours(&self) -> Option<Vec<(DateTime<Tz>, DateTime<Tz>)>> {
+        Some(self.trading_sessions().ok()?.into_iter().map(|session| (session.start, session.end)).collect())
+    }
+    /// Returns the open and close of the liquid trading sessions (i.e. trading calendar), `None` if they cannot be parsed.
     pub fn liquid_hours(&self) -> Option<Vec<(DateTime<Tz>, DateTime<Tz>)>> {
-
-        let mut liq_hours_it = self.liquid_hours.as_ref()?.split(";");
-        let mut ret = Vec::new();
-        while let Some(liq_hours) = liq_hours_it.next() {
-            if liq_hours.contains("CLOSED") {continue}
-            else {
-                let mut hours_it = liq_hours.split("-");
-                let open_str = hours_it.next()?;
-                let close_str = hours_it.next()?;
-                let open_dt = NaiveDateTime::parse_from_str(open_str, "%Y%m%d:%H%M").unwrap();
-                let close_dt = NaiveDateTime::parse_from_str(close_str, "%Y%m%d:%H%M").unwrap();
-                if let Some(tz) = &self.timezone_id {
-                    if tz.contains("US/Eastern") {
-                        ret.push((US::Eastern.from_local_datetime(&open_dt).unwrap(), US::Eastern.from_local_datetime(&close_dt).unwrap()));
-                    }
-                    else {
-                        ret.push((UTC.from_local_datetime(&open_dt).unwrap(), UTC.from_local_datetime(&close_dt).unwrap()));
-                    }
-                } 
-            }
-        }
-       Some(ret)
+        Some(self.liquid_sessions().ok()?.into_iter().map(|session| (session.start, session.end)).collect())
     }
     /// Returns the underlying contract.
-    pub fn contract(&self) -> &Option<Contract> {
-        &self.contract
+    pub fn contract(&self) -> Option<&Contract> {
+        self.contract.as_ref()
+    }
+    /// Returns the time zone of the exchange the contract is traded on.
+    pub fn time_zone(&self) -> Option<Tz> {
+        calendar::parse_time_zone(self.timezone_id.as_ref()?)
+    }
+    /// Returns the CUSIP of a bond.
+    pub fn cusip(&self) -> Option<&str> {
+        self.cusip.as_deref()
+    }
+    /// Returns the annual coupon rate of a bond in percent.
+    pub fn coupon(&self) -> Option<Decimal> {
+        self.coupon
+    }
+    /// Returns the coupon type of a bond, e.g. `FIXED` or `ZERO`.
+    pub fn coupon_type(&self) -> Option<&str> {
+        self.coupon_type.as_deref()
+    }
+    /// Returns the maturity date of a bond.
+    pub fn maturity(&self) -> Option<NaiveDate> {
+        self.maturity
+    }
+    /// Returns the issue date of a bond.
+    pub fn issue_date(&self) -> Option<NaiveDate> {
+        self.issue_date
+    }
+    /// Returns the credit ratings of a bond.
+    pub fn ratings(&self) -> Option<&str> {
+        self.ratings.as_deref()
+    }
+    /// Returns the bond type, e.g. a government or corporate bond.
+    pub fn bond_type(&self) -> Option<&str> {
+        self.bond_type.as_deref()
+    }
+    /// Returns true if the bond can be called by the issuer.
+    pub fn is_callable(&self) -> Option<bool> {
+        self.callable
+    }
+    /// Returns true if the bond can be sold back to the issuer.
+    pub fn is_putable(&self) -> Option<bool> {
+        self.putable
     }
+    /// Returns true if the bond can be converted to stock.
+    pub fn is_convertible(&self) -> Option<bool> {
+        self.convertible
+    }
+    /// Returns the date of the next call or put option of a bond, its type and whether it is a partial call or put.
+    pub fn next_option(&self) -> Option<(NaiveDate, Option<&str>, Option<bool>)> {
+        Some((self.next_option_date?, self.next_option_type.as_deref(), self.next_option_partial))
+    }
+    /// Returns additional descriptions of a bond.
+    pub fn notes(&self) -> Option<&str> {
+        self.notes.as_deref()
+    }
+    /// Returns the minimum price increment of the contract.
+    pub fn min_tick(&self) -> Option<Decimal> {
+        self.min_tick
+    }
+    /// Returns the market name, usually the trading class.
+    pub fn market_name(&self) -> Option<&str> {
+        self.market_name.as_deref()
+    }
+    /// Returns the factor by which prices are multiplied to obtain the quoted price.
+    pub fn price_magnifier(&self) -> Option<i32> {
+        self.price_magnifier
+    }
+    /// Returns the order types supported by the contract, e.g. `LMT` and `TRAIL`.
+    pub fn order_types(&self) -> Vec<&str> {
+        split_list(&self.order_types)
+    }
+    /// Returns the exchanges the contract can be routed to.
+    pub fn valid_exchanges(&self) -> Vec<&str> {
+        split_list(&self.valid_exchanges)
+    }
+    /// Returns the IDs of the market rules in the order of `valid_exchanges`.
+    pub fn market_rule_ids(&self) -> Vec<i32> {
+        split_list(&self.market_rule_ids).into_iter().filter_map(|id| id.parse().ok()).collect()
+    }
+    /// Returns the con_id of the underlying of derivatives.
+    pub fn under_con_id(&self) -> Option<i32> {
+        self.under_con_id
+    }
+    /// Returns the symbol of the underlying of derivatives.
+    pub fn under_symbol(&self) -> Option<&str> {
+        self.under_symbol.as_deref()
+    }
+    /// Returns the security type of the underlying of derivatives.
+    pub fn under_sec_type(&self) -> Option<&enums::SecType> {
+        self.under_sec_type.as_ref()
+    }
+    /// Returns the descriptive name of the contract, e.g. the company name.
+    pub fn long_name(&self) -> Option<&str> {
+        self.long_name.as_deref()
+    }
+    /// Returns the contract month `yyyymm` of derivatives.
+    pub fn contract_month(&self) -> Option<&str> {
+        self.contract_month.as_deref()
+    }
+    /// Returns the industry classification, e.g. `Technology`.
+    pub fn industry(&self) -> Option<&str> {
+        self.industry.as_deref()
+    }
+    /// Returns the industry category, e.g. `Computers`.
+    pub fn category(&self) -> Option<&str> {
+        self.category.as_deref()
+    }
+    /// Returns the industry subcategory.
+    pub fn subcategory(&self) -> Option<&str> {
+        self.subcategory.as_deref()
+    }
+    /// Returns the time zone ID of the exchange as sent by the TWS, see `time_zone` for the parsed time zone.
+    pub fn time_zone_id(&self) -> Option<&str> {
+        self.timezone_id.as_deref()
+    }
+    /// Returns the economic value rule, e.g. `aapl,1.0` for options.
+    pub fn ev_rule(&self) -> Option<&str> {
+        self.ev_rule.as_deref()
+    }
+    /// Returns the economic value multiplier, `None` if it is not set or not a number.
+    pub fn ev_multiplier(&self) -> Option<Decimal> {
+        parse_decimal(&self.ev_multiplier)
+    }
+    /// Returns the aggregated group of the contract.
+    pub fn agg_group(&self) -> Option<i32> {
+        self.agg_group
+    }
+    /// Returns the security IDs of the contract as pairs of ID type and ID, e.g. (`ISIN`, `US0378331005`).
+    pub fn sec_id_list(&self) -> &[(String, String)] {
+        self.sec_id_list.as_deref().unwrap_or_default()
+    }
+    /// Returns the expiration date of derivatives.
+    pub fn real_expiration_date(&self) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(self.real_expiration_date.as_ref()?.trim(), "%Y%m%d").ok()
+    }
+    /// Returns the last trade time of derivatives as sent by the TWS, in the time zone of the exchange.
+    pub fn last_trade_time(&self) -> Option<&str> {
+        self.last_trade_time.as_deref()
+    }
+    /// Returns the stock type, e.g. `COMMON`, `ETF` or `ADR`.
+    pub fn stock_type(&self) -> Option<&str> {
+        self.stock_type.as_deref()
+    }
+    /// Returns the description appendix, e.g. the issuer of a bond.
+    pub fn desc_append(&self) -> Option<&str> {
+        self.desc_append.as_deref()
+    }
+    /// Returns the minimum order size.
+    pub fn min_size(&self) -> Option<Decimal> {
+        parse_decimal(&self.min_size)
+    }
+    /// Returns the increment of the order size.
+    pub fn size_increment(&self) -> Option<Decimal> {
+        parse_decimal(&self.size_increment)
+    }
+    /// Returns the suggested increment of the order size.
+    pub fn suggested_size_increment(&self) -> Option<Decimal> {
+        parse_decimal(&self.suggested_size_increment)
+    }
+    /// Returns the ID of the market rule defining the price increments on the given exchange.
+    pub fn market_rule_id(&self, exchange: &str) -> Option<i32> {
+        let exchanges = self.valid_exchanges.as_ref()?.split(',');
+        let rule_ids = self.market_rule_ids.as_ref()?.split(',');
+        exchanges.zip(rule_ids)
+            .find(|(exch, _)| exch.eq_ignore_ascii_case(exchange))
+            .and_then(|(_, id)| id.trim().parse().ok())
+    }
+}
+
+/// A contract matching a symbol search, see `IBClient::search_symbols`.
+#[derive(Debug,Clone)]
+pub struct ContractDescription {
+    pub(crate) contract: Option<Contract>,
+    pub(crate) derivative_sec_types_list: Option<Vec<String>>,
+}
+
+impl ContractDescription {
+    /// Returns the contract with con_id, symbol, security type, primary exchange and currency.
+    pub fn contract(&self) -> Option<&Contract> {
+        self.contract.as_ref()
+    }
+    /// Returns the security types of the derivatives of the contract, e.g. `OPT` and `WAR`.
+    pub fn derivative_sec_types(&self) -> &[String] {
+        self.derivative_sec_types_list.as_deref().unwrap_or_default()
+    }
+}
+
+pub type ContractDescriptionList = Vec<ContractDescription>;
+
+
+/// A cache of qualified contracts keyed by the contract specification they were qualified from, used by
+/// `IBClient::qualify`. Clones share the same cache, so one cache can serve several clients. A cache opened from
+/// a file is written back whenever a contract is added. Persistence requires the `serde` feature.
+#[derive(Debug,Clone,Default)]
+pub struct ContractCache {
+    contracts: Arc<Mutex<HashMap<String, Contract>>>,
+    #[cfg(feature = "serde")]
+    path: Option<Arc<PathBuf>>
 }
 
-pub(crate) struct ContractDescription {
-    contract: Option<Contract>,
-    derivative_sec_types_list: Option<Vec<String>>,
+impl ContractCache {
+    /// Creates an in-memory cache.
+    pub fn new() -> Self {
+        ContractCache::default()
+    }
+    /// Opens a cache persisted as JSON in the given file, which is created when the first contract is added.
+    #[cfg(feature = "serde")]
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let contracts: HashMap<String, Contract> = match fs::File::open(path.as_ref()) {
+            Ok(file) => serde_json::from_reader(io::BufReader::new(file))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(err) => return Err(err)
+        };
+        Ok(ContractCache {contracts: Arc::new(Mutex::new(contracts)), path: Some(Arc::new(path.as_ref().to_path_buf()))})
+    }
+    fn key(spec: &Contract) -> String {
+        spec.encode().replace('\0', "|")
+    }
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Contract>> {
+        self.contracts.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+    /// Returns the qualified contract for the contract specification.
+    pub fn get(&self, spec: &Contract) -> Option<Contract> {
+        self.lock().get(&ContractCache::key(spec)).cloned()
+    }
+    /// Adds a qualified contract for the contract specification and writes the cache if it is persisted.
+    pub(crate) fn insert(&self, spec: &Contract, qualified: Contract) {
+        self.lock().insert(ContractCache::key(spec), qualified);
+        if let Err(err) = self.save() {
+            warn!("Contract cache could not be saved: {}", err);
+        }
+    }
+    /// Writes the cache to its file. Does nothing for in-memory caches.
+    pub fn save(&self) -> io::Result<()> {
+        #[cfg(feature = "serde")]
+        if let Some(path) = &self.path {
+            let tmp_path = path.with_extension("tmp");
+            serde_json::to_writer(io::BufWriter::new(fs::File::create(&tmp_path)?), &*self.lock())?;
+            fs::rename(&tmp_path, path.as_ref())?;
+        }
+        Ok(())
+    }
+    /// Removes all contracts, e.g. after contract specifications were changed by the exchange.
+    pub fn clear(&self) -> io::Result<()> {
+        self.lock().clear();
+        self.save()
+    }
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
 }
 
-pub(crate) type ContractDescriptionList = Vec<ContractDescription>;
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
 
+    #[test]
+    fn cache_round_trip() {
+        let path = std::env::temp_dir().join(format!("rs_ib_api_contract_cache_{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let spec = Contract::stock_us_smart("AAPL");
+        let qualified = Contract::stock_us_smart("AAPL").with_con_id(265598).with_primary_exchange("NASDAQ");
+        let cache = ContractCache::open(&path).unwrap();
+        assert!(cache.is_empty());
+        cache.insert(&spec, qualified.clone());
+        let reopened = ContractCache::open(&path).unwrap();
+        assert_eq!(reopened.len(), 1);
+        assert_eq!(reopened.get(&spec), Some(qualified));
+        assert_eq!(reopened.get(&Contract::stock_us_smart("MSFT")), None);
+        reopened.clear().unwrap();
+        assert!(ContractCache::open(&path).unwrap().is_empty());
+        fs::remove_file(&path).unwrap();
+    }
+}
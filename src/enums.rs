@@ -8,10 +8,12 @@ use enum_ordinalize;
 
 pub(crate) mod constants {
     pub(crate) const CLIENT_VERSION: i32 = 66;
-    pub(crate) const MIN_SERVER_VER_PRICE_MGMT_ALGO: i32 = 151;
     pub(crate) const MIN_CLIENT_VER: i32 = 100;
-    pub(crate) const MAX_CLIENT_VER: i32 = MIN_SERVER_VER_PRICE_MGMT_ALGO;
+    pub(crate) const MIN_SERVER_VER_HISTORICAL_SCHEDULE: i32 = 165;
+    //the decoders expect the formats of server version 164 and newer (contract data without version field, sizes in shares)
+    pub(crate) const MAX_CLIENT_VER: i32 = MIN_SERVER_VER_HISTORICAL_SCHEDULE;
     pub(crate) const COMPETE_AGAINST_BEST_OFFSET_UP_TO_MID: f64 = f64::INFINITY;
+    pub(crate) const MAX_HISTORICAL_TICKS: usize = 1000;
 }
 
 #[derive(FromPrimitive)]
@@ -92,7 +94,8 @@ pub enum Incoming {
     TickByTick                              = 99,
     OrderBound                               = 100,
     CompletedOrder                            = 101,
-    CompletedOrdersEnd                        = 102
+    CompletedOrdersEnd                        = 102,
+    HistoricalSchedule                        = 106
 }
 
 impl FromStr for Incoming {
@@ -202,6 +205,7 @@ pub struct ParseEnumError;
 // Some enums are only for encoding and implement the encode method (might make it a trait)
 
 #[derive(FromPrimitive,Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TickType {
     BidSize,
     Bid,
@@ -313,6 +317,7 @@ impl FromStr for TickType {
 impl Decodable for TickType {}
 
 #[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GenericTickType {
     ShortableData,
     HistoricData,
@@ -338,6 +343,7 @@ impl Encodable for GenericTickType {
 }
 
 #[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MarketDataType {
     RealTime = 1,
     Frozen = 2,
@@ -373,6 +379,7 @@ impl FromStr for MarketDataType {
 impl Decodable for MarketDataType {}
 
 #[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FundamentalDataType {
     Snapshot,
     FinSummary,
@@ -392,7 +399,8 @@ impl Encodable for FundamentalDataType {
         }.to_string()
     }
 }
-#[derive(Debug,PartialEq,Eq,Clone)]
+#[derive(Debug,PartialEq,Eq,PartialOrd,Ord,Hash,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SecType {
     Stock,
     Option,
@@ -406,6 +414,9 @@ pub enum SecType {
     Commodity,
     News,
     MutualFund,
+    Cfd,
+    Crypto,
+    ContinuousFuture,
 }
 
 impl Encodable for SecType {
@@ -423,6 +434,9 @@ impl Encodable for SecType {
             SecType::Commodity => "CMDTY\0",
             SecType::News => "NEWS\0",
             SecType::MutualFund => "FUND\0",
+            SecType::Cfd => "CFD\0",
+            SecType::Crypto => "CRYPTO\0",
+            SecType::ContinuousFuture => "CONTFUT\0",
         }.to_string()
     }
 }
@@ -443,6 +457,9 @@ impl FromStr for SecType {
             "CMDTY" => SecType::Commodity,
             "NEWS" => SecType::News,
             "FUND" => SecType::MutualFund,
+            "CFD" => SecType::Cfd,
+            "CRYPTO" => SecType::Crypto,
+            "CONTFUT" => SecType::ContinuousFuture,
             &_ => return Err(ParseEnumError)
         };
         Ok(res)
@@ -450,7 +467,8 @@ impl FromStr for SecType {
 }
 
 impl Decodable for SecType {}
-#[derive(Debug,Clone)]
+#[derive(Debug,PartialEq,Eq,PartialOrd,Ord,Hash,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OptionRight {
     Undefined,
     Put,
@@ -485,9 +503,11 @@ impl FromStr for OptionRight {
 
 impl Decodable for OptionRight {}
 #[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SecIdType {
     Isin,
     Cusip,
+    Figi,
 }
 
 impl Encodable for SecIdType {
@@ -495,11 +515,25 @@ impl Encodable for SecIdType {
         match self {
             SecIdType::Isin => "ISIN\0",
             SecIdType::Cusip => "CUSIP\0",
+            SecIdType::Figi => "FIGI\0",
         }.to_string()
     }
 }
 
-#[derive(Debug,Clone)]
+impl FromStr for SecIdType {
+    type Err = ParseEnumError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ISIN" => Ok(SecIdType::Isin),
+            "CUSIP" => Ok(SecIdType::Cusip),
+            "FIGI" => Ok(SecIdType::Figi),
+            &_ => Err(ParseEnumError)
+        }
+    }
+}
+
+#[derive(Debug,PartialEq,Eq,PartialOrd,Ord,Hash,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ComboAction {
     Buy,
     Sell,
@@ -531,6 +565,7 @@ impl FromStr for ComboAction {
 impl Decodable for ComboAction { }
 
 #[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OptionOpenClose {
     Same,
     Open,
@@ -565,6 +600,7 @@ impl FromStr for OptionOpenClose {
 impl Decodable for OptionOpenClose {}
 
 #[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ShortSaleSlot {
     NoSlot,
     Broker,
@@ -596,6 +632,7 @@ impl FromStr for ShortSaleSlot {
 impl Decodable for ShortSaleSlot {}
 
 #[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Action {
     Buy,
     Sell,
@@ -636,6 +673,7 @@ impl FromStr for Action {
 impl Decodable for Action {}
 
 #[derive(Debug,PartialEq,Eq,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OrderType {
     NoOrderType, //only legit for deltaNeutralOrderType
     Limit,
@@ -742,6 +780,7 @@ impl Default for OrderType {
 }
 
 #[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TriggerMethod {
     Default,
     DoubleBidAsk,
@@ -785,6 +824,7 @@ impl FromStr for TriggerMethod {
 impl Decodable for TriggerMethod {}
 
 #[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TimeInForce {
     Day,
     GoodTillCancel,
@@ -828,6 +868,7 @@ impl FromStr for TimeInForce {
 impl Decodable for TimeInForce {}
 
 #[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Rule80A {
     Individual,
     Agency,
@@ -877,6 +918,7 @@ impl FromStr for Rule80A {
 impl Decodable for Rule80A {}
 
 #[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OrderOpenClose {
     Open,
     Close,
@@ -905,6 +947,7 @@ impl FromStr for OrderOpenClose {
 impl Decodable for OrderOpenClose {}
 
 #[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Origin {
     Customer,
     Firm,
@@ -936,6 +979,7 @@ impl FromStr for Origin {
 impl Decodable for Origin {}
 
 #[derive(FromPrimitive,Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AuctionStrategy {
     NoAuctionStrategy,
     Match,
@@ -971,6 +1015,7 @@ impl FromStr for AuctionStrategy {
 impl Decodable for AuctionStrategy {}
 
 #[derive(FromPrimitive,Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OCAType {
     NoOCAType,
     CancelWithBlock,
@@ -1006,6 +1051,7 @@ impl FromStr for OCAType {
 impl Decodable for OCAType {}
 
 #[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum VolatilityType {
     NoVolType,
     Daily,
@@ -1037,6 +1083,7 @@ impl FromStr for VolatilityType {
 impl Decodable for VolatilityType {}
 
 #[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ReferencePriceType {
     NoRefPriceType,
     Average,
@@ -1068,6 +1115,7 @@ impl FromStr for ReferencePriceType {
 impl Decodable for ReferencePriceType {}
 
 #[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BasisPointsType {
     Undefined,
 }
@@ -1093,6 +1141,7 @@ impl FromStr for BasisPointsType {
 impl Decodable for BasisPointsType {}
 
 #[derive(PartialEq,Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum HedgeType {
     Undefined,
     Delta,
@@ -1130,6 +1179,7 @@ impl FromStr for HedgeType {
 impl Decodable for HedgeType {}
 
 #[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ClearingIntent {
     InteractiveBrokers,
     Away,
@@ -1161,6 +1211,7 @@ impl FromStr for ClearingIntent {
 impl Decodable for ClearingIntent {}
 
 #[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum UsePriceMgmtAlgo {
     DontUse,
     Use
@@ -1189,6 +1240,7 @@ impl FromStr for UsePriceMgmtAlgo {
 impl Decodable for UsePriceMgmtAlgo {}
 
 #[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Side {
     Long,
     Short,
@@ -1218,6 +1270,7 @@ impl Decodable for Side {}
 
 #[derive(FromPrimitive,Debug,Clone)]
 #[derive(enum_ordinalize::Ordinalize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OrderConditionType {
     Price = 1,
     Time = 3,
@@ -1322,6 +1375,8 @@ impl FromStr for IBAccountField {
 
 impl Decodable for IBAccountField {}
 
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum HistoricalDataType {
     //AdjustedLast is not included here, because it's special!
     Trades,
@@ -1336,7 +1391,8 @@ pub enum HistoricalDataType {
     YieldBid,
     YieldAsk,
     YieldBidAsk,
-    YieldLast
+    YieldLast,
+    Schedule
 }
 
 impl Encodable for HistoricalDataType {
@@ -1355,12 +1411,15 @@ impl Encodable for HistoricalDataType {
             HistoricalDataType::YieldBid => "YIELD_BID\0",
             HistoricalDataType::YieldAsk => "YIELD_ASK\0",
             HistoricalDataType::YieldBidAsk => "YIELD_BID_ASK\0",
-            HistoricalDataType::YieldLast => "YIELD_LAST\0"
+            HistoricalDataType::YieldLast => "YIELD_LAST\0",
+            HistoricalDataType::Schedule => "SCHEDULE\0"
 
         }.to_string()
     }
 }
 
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum HistoricalDataBarSize {
     OneSec,
     FiveSecs,
@@ -1414,6 +1473,54 @@ impl Encodable for HistoricalDataBarSize {
     }
 }
 
+impl HistoricalDataBarSize {
+    /// Returns the length of a bar. Months are counted as 30 days.
+    pub fn span(&self) -> chrono::Duration {
+        use HistoricalDataBarSize::*;
+        match self {
+            OneSec => chrono::Duration::seconds(1),
+            FiveSecs => chrono::Duration::seconds(5),
+            TenSecs => chrono::Duration::seconds(10),
+            FifteenSecs => chrono::Duration::seconds(15),
+            ThirtySecs => chrono::Duration::seconds(30),
+            OneMin => chrono::Duration::minutes(1),
+            TwoMins => chrono::Duration::minutes(2),
+            ThreeMins => chrono::Duration::minutes(3),
+            FiveMins => chrono::Duration::minutes(5),
+            TenMins => chrono::Duration::minutes(10),
+            FifteenMins => chrono::Duration::minutes(15),
+            TwentyMins => chrono::Duration::minutes(20),
+            ThirtyMins => chrono::Duration::minutes(30),
+            OneHour => chrono::Duration::hours(1),
+            TwoHours => chrono::Duration::hours(2),
+            ThreeHours => chrono::Duration::hours(3),
+            FourHours => chrono::Duration::hours(4),
+            EightHours => chrono::Duration::hours(8),
+            OneDay => chrono::Duration::days(1),
+            OneWeek => chrono::Duration::weeks(1),
+            OneMonth => chrono::Duration::days(30),
+        }
+    }
+    /// Returns the longest duration that can be requested with this bar size in a single historical data request,
+    /// according to the limits documented by IB.
+    pub fn max_duration(&self) -> HistoricalDataDuration {
+        use HistoricalDataBarSize::*;
+        match self {
+            OneSec => HistoricalDataDuration::Seconds(1800),
+            FiveSecs => HistoricalDataDuration::Seconds(3600),
+            TenSecs | FifteenSecs => HistoricalDataDuration::Seconds(14400),
+            ThirtySecs => HistoricalDataDuration::Seconds(28800),
+            OneMin => HistoricalDataDuration::Days(1),
+            TwoMins => HistoricalDataDuration::Days(2),
+            ThreeMins | FiveMins | TenMins | FifteenMins | TwentyMins => HistoricalDataDuration::Weeks(1),
+            ThirtyMins | OneHour | TwoHours | ThreeHours | FourHours | EightHours => HistoricalDataDuration::Months(1),
+            OneDay | OneWeek | OneMonth => HistoricalDataDuration::Years(1)
+        }
+    }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum HistoricalDataDuration {
     Seconds(i32),
     Days(i32),
@@ -1433,4 +1540,61 @@ impl Encodable for HistoricalDataDuration {
             Years(count) => count.to_string() + " Y\0"
         }
     }
-}
\ No newline at end of file
+}
+
+impl HistoricalDataDuration {
+    /// Returns the shortest calendar span covered by the duration. Months are counted as 28 days and years as 365 days.
+    pub fn min_span(&self) -> chrono::Duration {
+        use HistoricalDataDuration::*;
+        match self {
+            Seconds(count) => chrono::Duration::seconds(*count as i64),
+            Days(count) => chrono::Duration::days(*count as i64),
+            Weeks(count) => chrono::Duration::weeks(*count as i64),
+            Months(count) => chrono::Duration::days(28 * *count as i64),
+            Years(count) => chrono::Duration::days(365 * *count as i64)
+        }
+    }
+    /// Returns the longest calendar span covered by the duration. Months are counted as 31 days and years as 366 days.
+    pub fn max_span(&self) -> chrono::Duration {
+        use HistoricalDataDuration::*;
+        match self {
+            Months(count) => chrono::Duration::days(31 * *count as i64),
+            Years(count) => chrono::Duration::days(366 * *count as i64),
+            _ => self.min_span()
+        }
+    }
+}
+pub enum HistoricalTicksType {
+    Trades,
+    BidAsk,
+    Midpoint
+}
+
+impl Encodable for HistoricalTicksType {
+    fn encode(&self) -> String {
+        match self {
+            HistoricalTicksType::Trades => "TRADES\0",
+            HistoricalTicksType::BidAsk => "BID_ASK\0",
+            HistoricalTicksType::Midpoint => "MIDPOINT\0"
+        }.to_string()
+    }
+}
+
+pub enum HistogramPeriod {
+    Days(i32),
+    Weeks(i32),
+    Months(i32),
+    Years(i32)
+}
+
+impl Encodable for HistogramPeriod {
+    fn encode(&self) -> String {
+        use HistogramPeriod::*;
+        match self {
+            Days(count) => count.to_string() + " days\0",
+            Weeks(count) => count.to_string() + " weeks\0",
+            Months(count) => count.to_string() + " months\0",
+            Years(count) => count.to_string() + " years\0"
+        }
+    }
+}
@@ -1,13 +1,16 @@
 use std::collections::HashSet;
 use rust_decimal::prelude::*;
-use chrono::NaiveDateTime;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
 use crate::account::Position;
 use crate::{contract, opt_params};
 use crate::utils::ib_message::decode;
 use crate::order;
 use crate::bars;
+use crate::ticks;
+use crate::market_rule;
+use crate::calendar;
 use crate::enums::*;
-use log::debug;
+use log::{debug, warn};
 
 use enumset::EnumSetType;
 use enumset::EnumSet;
@@ -49,12 +52,28 @@ pub(crate) enum IBFrame {
     SizeTick{id: i32, kind: TickType, size: i32},
     StringTick{id: i32, kind: TickType, val: Option<String>},
     GenericTick{id: i32, kind: TickType, val: f64},
-    Bars{id: i32, data: bars::BarSeries},
+    Bars{id: i32, data: bars::RawBarSeries},
+    BarUpdate{id: i32, bar: bars::RawBar},
+    HistoricalTicks{id: i32, ticks: ticks::HistoricalTicks, done: bool},
+    HeadTimestamp{id: i32, time: DateTime<Utc>},
+    Histogram{id: i32, data: Vec<bars::HistogramEntry>},
+    HistoricalSchedule{id: i32, schedule: calendar::TradingSchedule},
+    MarketRule(market_rule::MarketRule),
     OptParams{id: i32, data: opt_params::OptParams},
+    OptParamsEnd(i32),
+    SymbolSamples{id: i32, descriptions: contract::ContractDescriptionList},
     Error{id: Option<i32>, code: Option<i32>, msg: Option<String>},
     NotImplemented
 }
 
+/// Decodes a date sent as `yyyymmdd` (optionally followed by a time) or `mm/dd/yyyy`.
+fn decode_date(it: &mut std::str::Split<'_, &str>) -> Option<NaiveDate> {
+    let val: String = decode(it)?;
+    NaiveDate::parse_from_str(val.get(..8)?, "%Y%m%d")
+        .or_else(|_| NaiveDate::parse_from_str(&val, "%m/%d/%Y"))
+        .ok()
+}
+
 impl IBFrame {
     pub fn parse (msg: &[u8]) -> Option<Self> {
         let utf8msg = String::from_utf8_lossy(msg);
@@ -193,6 +212,61 @@ impl IBFrame {
                     }
                 }
             },
+            Incoming::BondContractData => {
+                let req_id = decode(&mut it)?;
+                let mut contract = contract::Contract {
+                    symbol: decode(&mut it),
+                    sec_type: decode(&mut it),
+                    ..Default::default()
+                };
+                let mut details = contract::ContractDetails {
+                    cusip: decode(&mut it),
+                    coupon: decode(&mut it),
+                    ..Default::default()
+                };
+                //maturity date, optionally followed by the last trade time and time zone
+                let maturity: Option<String> = decode(&mut it);
+                if let Some(maturity) = maturity {
+                    let mut parts = maturity.split_whitespace();
+                    details.maturity = parts.next().and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok());
+                    details.last_trade_time = parts.next().map(str::to_string);
+                    details.timezone_id = parts.next().map(str::to_string);
+                }
+                details.issue_date = decode_date(&mut it);
+                details.ratings = decode(&mut it);
+                details.bond_type = decode(&mut it);
+                details.coupon_type = decode(&mut it);
+                details.convertible = decode(&mut it);
+                details.callable = decode(&mut it);
+                details.putable = decode(&mut it);
+                details.desc_append = decode(&mut it);
+                contract.exchange = decode(&mut it);
+                contract.currency = decode(&mut it);
+                details.market_name = decode(&mut it);
+                contract.trading_class = decode(&mut it);
+                contract.con_id = decode(&mut it);
+                details.min_tick = decode(&mut it);
+                details.order_types = decode(&mut it);
+                details.valid_exchanges = decode(&mut it);
+                details.next_option_date = decode_date(&mut it);
+                details.next_option_type = decode(&mut it);
+                details.next_option_partial = decode(&mut it);
+                details.notes = decode(&mut it);
+                details.long_name = decode(&mut it);
+                details.ev_rule = decode(&mut it);
+                details.ev_multiplier = decode(&mut it);
+                let sec_id_list_count: Option<usize> = decode(&mut it);
+                details.sec_id_list = sec_id_list_count.map(|count| (0..count)
+                    .map(|_| (decode(&mut it).unwrap_or_default(), decode(&mut it).unwrap_or_default()))
+                    .collect());
+                details.agg_group = decode(&mut it);
+                details.market_rule_ids = decode(&mut it);
+                details.min_size = decode(&mut it);
+                details.size_increment = decode(&mut it);
+                details.suggested_size_increment = decode(&mut it);
+                details.contract = Some(contract);
+                Some(IBFrame::ContractDetails {req_id, contract_details: details})
+            },
             Incoming::ContractDataEnd => {
                 it.next(); //skip version
                 Some(IBFrame::ContractDetailsEnd(decode(&mut it)?))
@@ -568,25 +642,172 @@ impl IBFrame {
                 let id = decode(&mut it)?;
                 let start_dt = decode(&mut it);
                 let end_dt = decode(&mut it);
-                let n_bars = decode(&mut it);
-                let data = if let Some(nb) = n_bars {
-                    if nb > 0 {
-                        let mut bar_data = Vec::with_capacity(nb);
-                        for i in 0..nb {
-                            bar_data.push(bars::Bar {
-                                t_stamp: decode(&mut it),
-                                open: decode(&mut it),
-                                high: decode(&mut it),
-                                low: decode(&mut it),
-                                close: decode(&mut it),
-                                volume: decode(&mut it),
-                                wap: decode(&mut it),
-                                count: decode(&mut it)
-                            });
-                        }
-                        Some(bar_data)} else {None}
-                } else {None};
-                Some(IBFrame::Bars{id, data: bars::BarSeries{start_dt, end_dt, n_bars, data}})
+                let n_bars: usize = decode(&mut it).unwrap_or(0);
+                let mut data = Vec::new();
+                for _ in 0..n_bars {
+                    data.push(bars::RawBar {
+                        t_stamp: decode(&mut it),
+                        open: decode(&mut it),
+                        high: decode(&mut it),
+                        low: decode(&mut it),
+                        close: decode(&mut it),
+                        volume: decode(&mut it),
+                        wap: decode(&mut it),
+                        count: decode(&mut it)
+                    });
+                }
+                Some(IBFrame::Bars{id, data: bars::RawBarSeries{start_dt, end_dt, data}})
+            }
+            Incoming::HistoricalDataUpdate => {
+                let id = decode(&mut it)?;
+                let count = decode(&mut it);
+                let t_stamp = decode(&mut it);
+                let open = decode(&mut it);
+                let close = decode(&mut it);
+                let high = decode(&mut it);
+                let low = decode(&mut it);
+                let wap = decode(&mut it);
+                let volume = decode(&mut it);
+                Some(IBFrame::BarUpdate{id, bar: bars::RawBar {
+                    t_stamp, open, high, low, close, wap, volume, count
+                }})
+            }
+            Incoming::HistoricalTicks => {
+                let id = decode(&mut it)?;
+                let n_ticks: usize = decode(&mut it)?;
+                let mut data = Vec::new();
+                for _ in 0..n_ticks {
+                    let time: Option<i64> = decode(&mut it);
+                    it.next(); //skip unused field
+                    let price = decode(&mut it);
+                    let size = decode(&mut it);
+                    if let (Some(time), Some(price), Some(size)) = (time, price, size) {
+                        data.push(ticks::HistoricalTick {
+                            time: Utc.timestamp_opt(time, 0).single()?,
+                            price,
+                            size
+                        });
+                    }
+                }
+                Some(IBFrame::HistoricalTicks{id, ticks: ticks::HistoricalTicks::Midpoint(data), done: decode(&mut it).unwrap_or(true)})
+            }
+            Incoming::HistoricalTicksBidAsk => {
+                let id = decode(&mut it)?;
+                let n_ticks: usize = decode(&mut it)?;
+                let mut data = Vec::new();
+                for _ in 0..n_ticks {
+                    let time: Option<i64> = decode(&mut it);
+                    let mask: i32 = decode(&mut it).unwrap_or(0);
+                    let bid_price = decode(&mut it);
+                    let ask_price = decode(&mut it);
+                    let bid_size = decode(&mut it);
+                    let ask_size = decode(&mut it);
+                    if let (Some(time), Some(bid_price), Some(ask_price)) = (time, bid_price, ask_price) {
+                        data.push(ticks::HistoricalTickBidAsk {
+                            time: Utc.timestamp_opt(time, 0).single()?,
+                            bid_price,
+                            ask_price,
+                            bid_size: bid_size.unwrap_or_default(),
+                            ask_size: ask_size.unwrap_or_default(),
+                            ask_past_high: mask & 1 != 0,
+                            bid_past_low: mask & 2 != 0
+                        });
+                    }
+                }
+                Some(IBFrame::HistoricalTicks{id, ticks: ticks::HistoricalTicks::BidAsk(data), done: decode(&mut it).unwrap_or(true)})
+            }
+            Incoming::HistoricalTicksLast => {
+                let id = decode(&mut it)?;
+                let n_ticks: usize = decode(&mut it)?;
+                let mut data = Vec::new();
+                for _ in 0..n_ticks {
+                    let time: Option<i64> = decode(&mut it);
+                    let mask: i32 = decode(&mut it).unwrap_or(0);
+                    let price = decode(&mut it);
+                    let size = decode(&mut it);
+                    let exchange = decode(&mut it);
+                    let special_conditions = decode(&mut it);
+                    if let (Some(time), Some(price), Some(size)) = (time, price, size) {
+                        data.push(ticks::HistoricalTickLast {
+                            time: Utc.timestamp_opt(time, 0).single()?,
+                            price,
+                            size,
+                            exchange,
+                            special_conditions,
+                            past_limit: mask & 1 != 0,
+                            unreported: mask & 2 != 0
+                        });
+                    }
+                }
+                Some(IBFrame::HistoricalTicks{id, ticks: ticks::HistoricalTicks::Trades(data), done: decode(&mut it).unwrap_or(true)})
+            }
+            Incoming::HeadTimestamp => {
+                let id = decode(&mut it)?;
+                let time_str: String = decode(&mut it)?;
+                let time = match time_str.parse::<i64>() {
+                    Ok(epoch) => Utc.timestamp_opt(epoch, 0).single()?,
+                    Err(_) => NaiveDateTime::parse_from_str(&time_str, "%Y%m%d-%H:%M:%S").ok()?.and_utc()
+                };
+                Some(IBFrame::HeadTimestamp{id, time})
+            }
+            Incoming::HistoricalSchedule => {
+                let id = decode(&mut it)?;
+                let start: String = decode(&mut it)?;
+                let end: String = decode(&mut it)?;
+                let tz_id: String = decode(&mut it)?;
+                let time_zone = match calendar::parse_time_zone(&tz_id) {
+                    Some(tz) => tz,
+                    None => {
+                        warn!("Unknown time zone {} in trading schedule, using UTC.", tz_id);
+                        chrono_tz::UTC
+                    }
+                };
+                let n_sessions: usize = decode(&mut it)?;
+                let mut sessions = Vec::new();
+                for _ in 0..n_sessions {
+                    let session_start: Option<String> = decode(&mut it);
+                    let session_end: Option<String> = decode(&mut it);
+                    let ref_date: Option<String> = decode(&mut it);
+                    if let (Some(session_start), Some(session_end), Some(ref_date)) = (session_start, session_end, ref_date) {
+                        sessions.push(calendar::TradingSession {
+                            start: calendar::parse_schedule_time(&session_start, &time_zone)?,
+                            end: calendar::parse_schedule_time(&session_end, &time_zone)?,
+                            ref_date: chrono::NaiveDate::parse_from_str(&ref_date, "%Y%m%d").ok()?
+                        });
+                    }
+                }
+                Some(IBFrame::HistoricalSchedule{id, schedule: calendar::TradingSchedule {
+                    start: calendar::parse_schedule_time(&start, &time_zone)?,
+                    end: calendar::parse_schedule_time(&end, &time_zone)?,
+                    time_zone,
+                    sessions
+                }})
+            }
+            Incoming::HistogramData => {
+                let id = decode(&mut it)?;
+                let n_entries: usize = decode(&mut it)?;
+                let mut data = Vec::new();
+                for _ in 0..n_entries {
+                    let price = decode(&mut it);
+                    let size = decode(&mut it);
+                    if let (Some(price), Some(size)) = (price, size) {
+                        data.push(bars::HistogramEntry {price, size});
+                    }
+                }
+                Some(IBFrame::Histogram{id, data})
+            }
+            Incoming::MarketRule => {
+                let id = decode(&mut it)?;
+                let n_increments: usize = decode(&mut it)?;
+                let mut increments = Vec::new();
+                for _ in 0..n_increments {
+                    let low_edge = decode(&mut it);
+                    let increment = decode(&mut it);
+                    if let (Some(low_edge), Some(increment)) = (low_edge, increment) {
+                        increments.push(market_rule::PriceIncrement {low_edge, increment});
+                    }
+                }
+                Some(IBFrame::MarketRule(market_rule::MarketRule {id, increments}))
             }
             Incoming::SecurityDefinitionOptionParameter => {
                 let id: i32 = decode(&mut it)?;
@@ -596,7 +817,7 @@ impl IBFrame {
                 let multiplier = decode(&mut it);
                 let expirations_size = decode(&mut it)?;
                 let mut expirations = HashSet::new();
-                for i in 0..expirations_size {
+                for _ in 0..expirations_size {
                     let expiration = decode(&mut it);
                     if let Some(expir_val) = expiration {
                         expirations.insert(expir_val);
@@ -604,7 +825,7 @@ impl IBFrame {
                 }
                 let strikes_size = decode(&mut it)?;
                 let mut strikes = HashSet::new();
-                for i in 0..strikes_size {
+                for _ in 0..strikes_size {
                     let strike = decode(&mut it);
                     if let Some(strike_val) = strike {
                         strikes.insert(strike_val);
@@ -622,6 +843,31 @@ impl IBFrame {
                     }
                 })
             }
+            Incoming::SecurityDefinitionOptionParameterEnd => {
+                Some(IBFrame::OptParamsEnd(decode(&mut it)?))
+            }
+            Incoming::SymbolSamples => {
+                let id = decode(&mut it)?;
+                let n_descriptions: usize = decode(&mut it)?;
+                let mut descriptions = Vec::new();
+                for _ in 0..n_descriptions {
+                    let contract = contract::Contract {
+                        con_id: decode(&mut it),
+                        symbol: decode(&mut it),
+                        sec_type: decode(&mut it),
+                        primary_exchange: decode(&mut it),
+                        currency: decode(&mut it),
+                        ..Default::default()
+                    };
+                    let n_sec_types: usize = decode(&mut it)?;
+                    let sec_types = (0..n_sec_types).filter_map(|_| decode(&mut it)).collect();
+                    descriptions.push(contract::ContractDescription {
+                        contract: Some(contract),
+                        derivative_sec_types_list: Some(sec_types)
+                    });
+                }
+                Some(IBFrame::SymbolSamples {id, descriptions})
+            }
             Incoming::ErrMsg => {
                 it.next(); //skip version
                 Some(IBFrame::Error {
@@ -634,4 +880,126 @@ impl IBFrame {
         }
         
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encodes the fields of a message as sent by the TWS, without the length prefix.
+    fn message(fields: &[&str]) -> Vec<u8> {
+        fields.iter().map(|field| format!("{}\0", field)).collect::<String>().into_bytes()
+    }
+
+    fn contract_details(fields: &[&str]) -> contract::ContractDetails {
+        match IBFrame::parse(&message(fields)) {
+            Some(IBFrame::ContractDetails {req_id: 7, contract_details}) => contract_details,
+            _ => panic!("contract details not decoded")
+        }
+    }
+
+    #[test]
+    fn size_tick() {
+        match IBFrame::parse(&message(&["2", "6", "1", "0", "2500"])) {
+            Some(IBFrame::SizeTick {id: 1, kind: TickType::BidSize, size: 2500}) => (),
+            _ => panic!("size tick not decoded")
+        }
+    }
+
+    #[test]
+    fn head_timestamp() {
+        let time = |val: &str| match IBFrame::parse(&message(&["88", "3", val])) {
+            Some(IBFrame::HeadTimestamp {id: 3, time}) => Some(time),
+            _ => None
+        };
+        let expected = Utc.with_ymd_and_hms(2023, 3, 1, 14, 30, 0).single();
+        assert_eq!(time("1677681000"), expected);
+        assert_eq!(time("20230301-14:30:00"), expected);
+        assert_eq!(time("2023-03-01 14:30:00"), None);
+    }
+
+    #[test]
+    fn contract_data_without_version() {
+        let details = contract_details(&["10", "7", "AAPL", "STK", "", "0", "", "SMART", "USD", "AAPL", "NMS", "NMS", "265598",
+            "0.01", "", "ACTIVETIM,AD,LMT", "SMART,NASDAQ", "1", "0", "APPLE INC", "NASDAQ", "", "Technology", "Computers",
+            "Computers", "US/Eastern", "20230301:0400-20230301:2000", "20230301:0930-20230301:1600", "", "", "1", "ISIN",
+            "US0378331005", "1", "", "", "26,26", "", "COMMON", "0.0001", "0.0001", "100"]);
+        let contract = details.contract().unwrap();
+        assert_eq!(contract.con_id(), Some(265598));
+        assert_eq!(contract.primary_exchange(), Some("NASDAQ"));
+        assert_eq!(details.long_name(), Some("APPLE INC"));
+        assert_eq!(details.sec_id_list(), &[("ISIN".to_string(), "US0378331005".to_string())]);
+        assert_eq!(details.market_rule_ids(), vec![26, 26]);
+        assert_eq!(details.stock_type(), Some("COMMON"));
+        assert_eq!(details.min_size(), Some(Decimal::new(1, 4)));
+        assert_eq!(details.suggested_size_increment(), Some(Decimal::new(100, 0)));
+    }
+
+    #[test]
+    fn bond_contract_data_without_version() {
+        let details = contract_details(&["18", "7", "US-T", "BOND", "912828C57", "2.25", "20240331", "20140331", "", "", "FIXED",
+            "0", "0", "0", "", "SMART", "USD", "US-T", "US-T", "12345", "0.0001", "LMT", "SMART", "", "", "", "", "US T 2 1/4 03/31/24",
+            "", "", "0", "1", "26", "1000", "1000", "1000"]);
+        assert_eq!(details.contract().unwrap().con_id(), Some(12345));
+        assert_eq!(details.cusip(), Some("912828C57"));
+        assert_eq!(details.long_name(), Some("US T 2 1/4 03/31/24"));
+        assert_eq!(details.market_rule_ids(), vec![26]);
+        assert_eq!(details.suggested_size_increment(), Some(Decimal::new(1000, 0)));
+    }
+
+    fn bond_details(maturity: &str, issue_date: &str, next_option_date: &str) -> contract::ContractDetails {
+        contract_details(&["18", "7", "IBM", "BOND", "459200HU8", "3.625", maturity, issue_date, "A-", "CORP", "FIXED",
+            "0", "1", "0", "IBM 3 5/8 02/12/24", "SMART", "USD", "IBM", "IBM", "123456", "0.001", "LMT", "SMART",
+            next_option_date, "Call", "1", "Callable at par", "IBM 3 5/8 02/12/24", "", "", "0", "1", "26", "2000", "1000", "1000"])
+    }
+
+    #[test]
+    fn bond_contract_data_fields() {
+        let details = bond_details("20240212", "20140212", "20231112");
+        assert_eq!(details.coupon(), Some(Decimal::new(3625, 3)));
+        assert_eq!(details.maturity(), NaiveDate::from_ymd_opt(2024, 2, 12));
+        assert_eq!(details.last_trade_time(), None);
+        assert_eq!(details.issue_date(), NaiveDate::from_ymd_opt(2014, 2, 12));
+        assert_eq!(details.ratings(), Some("A-"));
+        assert_eq!(details.coupon_type(), Some("FIXED"));
+        assert_eq!((details.is_convertible(), details.is_callable(), details.is_putable()), (Some(false), Some(true), Some(false)));
+        assert_eq!(details.desc_append(), Some("IBM 3 5/8 02/12/24"));
+        assert_eq!(details.next_option(), Some((NaiveDate::from_ymd_opt(2023, 11, 12).unwrap(), Some("Call"), Some(true))));
+        assert_eq!(details.notes(), Some("Callable at par"));
+        assert_eq!(details.min_size(), Some(Decimal::new(2000, 0)));
+    }
+
+    #[test]
+    fn bond_contract_data_dates() {
+        //the maturity may be followed by the last trade time and the time zone
+        let details = bond_details("20240212 16:00:00 US/Eastern", "02/12/2014", "11/12/2023");
+        assert_eq!(details.maturity(), NaiveDate::from_ymd_opt(2024, 2, 12));
+        assert_eq!(details.last_trade_time(), Some("16:00:00"));
+        assert_eq!(details.time_zone_id(), Some("US/Eastern"));
+        //dates are sent as yyyymmdd or mm/dd/yyyy
+        assert_eq!(details.issue_date(), NaiveDate::from_ymd_opt(2014, 2, 12));
+        assert_eq!(details.next_option().map(|(date, _, _)| date), NaiveDate::from_ymd_opt(2023, 11, 12));
+        let details = bond_details("", "", "");
+        assert_eq!((details.maturity(), details.issue_date(), details.next_option()), (None, None, None));
+    }
+
+    #[test]
+    fn execution_data() {
+        let execution = |time: &str| match IBFrame::parse(&message(&["11", "-1", "42", "265598", "AAPL", "STK", "", "0", "",
+            "", "ISLAND", "USD", "AAPL", "NMS", "0000e0d5.6401b6c4.01.01", time, "DU123456", "ISLAND", "BOT", "100",
+            "150.25", "1234", "1", "0", "100", "150.25", "", "", "", "", "2"])) {
+            Some(IBFrame::Execution(execution)) => execution,
+            _ => panic!("execution not decoded")
+        };
+        let tz = chrono_tz::UTC;
+        let fill = execution("20230301 09:30:05 US/Eastern");
+        assert_eq!((fill.order_id(), fill.contract().con_id()), (42, Some(265598)));
+        assert_eq!(fill.exec_id(), Some("0000e0d5.6401b6c4.01.01"));
+        assert!(matches!(fill.side(), Some(Side::Long)));
+        assert_eq!((fill.shares(), fill.price()), (Some(Decimal::new(100, 0)), Some(Decimal::new(15025, 2))));
+        assert_eq!(fill.last_liquidity(), Some(2));
+        assert_eq!(fill.time(&tz), Some(tz.with_ymd_and_hms(2023, 3, 1, 14, 30, 5).unwrap()));
+        assert_eq!(execution("20230301  09:30:05").time(&tz), Some(tz.with_ymd_and_hms(2023, 3, 1, 9, 30, 5).unwrap()));
+        assert_eq!(execution("").time(&tz), None);
+    }
+}
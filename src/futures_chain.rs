@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use chrono::NaiveDate;
+use log::warn;
+use rust_decimal::prelude::*;
+use crate::bars::{Bar, BarSeries};
+use crate::contract::{Contract, ContractDetails};
+
+/// A listed futures contract with its last trade date.
+#[derive(Debug,Clone)]
+pub struct ListedFuture {
+    pub last_trade_date: NaiveDate,
+    pub contract: Contract
+}
+
+/// A roll from one contract of a futures chain to the next. The `to` contract is used from `date` on.
+#[derive(Debug,Clone)]
+pub struct Roll {
+    pub date: NaiveDate,
+    pub from: Contract,
+    pub to: Contract
+}
+
+/// The listed contracts of a future as returned by `IBClient::req_futures_chain`, sorted by expiry.
+#[derive(Debug,Clone)]
+pub struct FuturesChain {
+    pub contracts: Vec<ListedFuture>
+}
+
+impl FuturesChain {
+    pub fn new(mut contracts: Vec<ListedFuture>) -> Self {
+        contracts.sort_by_key(|listed| listed.last_trade_date);
+        FuturesChain {contracts}
+    }
+    pub(crate) fn from_details(details: Vec<ContractDetails>) -> Self {
+        let contracts = details.into_iter().filter_map(|details| {
+            let contract = details.contract()?.clone();
+            //the last trade date might be followed by the last trade time
+            let last_trade_date = contract.last_trade_date_or_contract_month.as_ref()?.get(..8)?;
+            let last_trade_date = NaiveDate::parse_from_str(last_trade_date, "%Y%m%d").ok()?;
+            Some(ListedFuture {last_trade_date, contract})
+        }).collect();
+        FuturesChain::new(contracts)
+    }
+    /// Returns the last trade dates of the listed contracts.
+    pub fn expiries(&self) -> Vec<NaiveDate> {
+        self.contracts.iter().map(|listed| listed.last_trade_date).collect()
+    }
+    /// Returns the front contract at the given date when rolling `days_before_expiry` calendar days before the
+    /// last trade date.
+    pub fn front(&self, as_of: NaiveDate, days_before_expiry: i64) -> Option<&ListedFuture> {
+        self.contracts.iter().find(|listed| listed.last_trade_date - chrono::Duration::days(days_before_expiry) > as_of)
+    }
+    /// Returns the contract following the front contract at the given date.
+    pub fn next(&self, as_of: NaiveDate, days_before_expiry: i64) -> Option<&ListedFuture> {
+        let front = self.contracts.iter()
+            .position(|listed| listed.last_trade_date - chrono::Duration::days(days_before_expiry) > as_of)?;
+        self.contracts.get(front + 1)
+    }
+    /// Returns the contract with the highest volume, e.g. of the last daily bar of each contract. Contracts are
+    /// identified by con_id.
+    pub fn front_by_volume(&self, volumes: &HashMap<i32, Decimal>) -> Option<&ListedFuture> {
+        self.contracts.iter()
+            .filter_map(|listed| Some((listed, volumes.get(&listed.contract.con_id()?)?)))
+            .fold(None, |front: Option<(&ListedFuture, &Decimal)>, (listed, volume)| match front {
+                Some((_, max)) if max >= volume => front,
+                _ => Some((listed, volume))
+            })
+            .map(|(listed, _)| listed)
+    }
+    /// Returns the rolls between consecutive contracts, `days_before_expiry` calendar days before each last trade date.
+    pub fn roll_dates(&self, days_before_expiry: i64) -> Vec<Roll> {
+        self.contracts.windows(2).map(|pair| Roll {
+            date: pair[0].last_trade_date - chrono::Duration::days(days_before_expiry),
+            from: pair[0].contract.clone(),
+            to: pair[1].contract.clone()
+        }).collect()
+    }
+    /// Returns the rolls between consecutive contracts on the first day the volume of the next contract exceeds the
+    /// volume of the expiring one, given the daily bars of the contracts by con_id. Without such a day, the roll is
+    /// on the last trade date.
+    pub fn volume_roll_dates(&self, daily_bars: &HashMap<i32, BarSeries>) -> Vec<Roll> {
+        let volumes = |contract: &Contract| -> HashMap<NaiveDate, Decimal> {
+            contract.con_id().and_then(|con_id| daily_bars.get(&con_id))
+                .map(|series| series.data.iter().map(|bar| (bar.t_stamp.date_naive(), bar.volume)).collect())
+                .unwrap_or_default()
+        };
+        let mut rolls: Vec<Roll> = Vec::new();
+        for pair in self.contracts.windows(2) {
+            let to_volumes = volumes(&pair[1].contract);
+            let mut from_days: Vec<(NaiveDate, Decimal)> = volumes(&pair[0].contract).into_iter().collect();
+            from_days.sort_by_key(|(date, _)| *date);
+            let earliest = rolls.last().map_or(NaiveDate::MIN, |roll| roll.date);
+            let date = from_days.iter()
+                .filter(|(date, _)| *date > earliest && *date <= pair[0].last_trade_date)
+                .find(|(date, volume)| to_volumes.get(date).is_some_and(|to_volume| to_volume > volume))
+                .map_or(pair[0].last_trade_date, |(date, _)| *date);
+            rolls.push(Roll {date, from: pair[0].contract.clone(), to: pair[1].contract.clone()});
+        }
+        rolls
+    }
+    /// Stitches the bars of the rolled contracts, given by con_id, into one back-adjusted series: each contract is used
+    /// until its roll date and earlier prices are shifted by the difference of the closes of both contracts on the
+    /// last bar before the roll, so that the series has no jumps at the rolls.
+    pub fn stitch_back_adjusted(rolls: &[Roll], bars: &HashMap<i32, BarSeries>) -> BarSeries {
+        let series = |contract: &Contract| contract.con_id().and_then(|con_id| bars.get(&con_id));
+        let mut data: Vec<Bar> = Vec::new();
+        let mut adjustment = 0.0;
+        let mut until: Option<NaiveDate> = None;
+        //walk the contracts from the most recent to the oldest
+        let contracts = rolls.iter().rev()
+            .map(|roll| (&roll.to, Some(roll)))
+            .chain(rolls.first().map(|roll| (&roll.from, None)));
+        for (contract, roll) in contracts {
+            let from_date = roll.map(|roll| roll.date);
+            let mut segment: Vec<Bar> = series(contract).map_or_else(Vec::new, |series| series.data.iter()
+                .filter(|bar| from_date.is_none_or(|date| bar.t_stamp.date_naive() >= date))
+                .filter(|bar| until.is_none_or(|date| bar.t_stamp.date_naive() < date))
+                .cloned().collect());
+            for bar in &mut segment {
+                bar.open += adjustment;
+                bar.high += adjustment;
+                bar.low += adjustment;
+                bar.close += adjustment;
+                if bar.wap > Decimal::ZERO {
+                    bar.wap += Decimal::from_f64(adjustment).unwrap_or_default();
+                }
+            }
+            segment.append(&mut data);
+            data = segment;
+            if let Some(roll) = roll {
+                let close_before = |contract: &Contract| series(contract)
+                    .and_then(|series| series.data.iter().rev().find(|bar| bar.t_stamp.date_naive() < roll.date))
+                    .map(|bar| bar.close);
+                match (close_before(&roll.from), close_before(&roll.to)) {
+                    (Some(from), Some(to)) => adjustment += to - from,
+                    _ => warn!("No closes of both contracts before the roll on {}, the roll is not adjusted.", roll.date)
+                }
+                until = Some(roll.date);
+            }
+        }
+        BarSeries {start_dt: data.first().map(|bar| bar.t_stamp), end_dt: data.last().map(|bar| bar.t_stamp), data}
+    }
+}
@@ -0,0 +1,515 @@
+use std::collections::VecDeque;
+use std::{error::Error, fmt};
+use std::fs;
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use tokio::time::{self, Duration, Instant};
+use crate::bars::{Bar, BarSeries};
+use crate::enums::{HistoricalDataBarSize, HistoricalDataDuration, HistoricalDataType, SecType};
+
+/// Returned if a historical data request violates the limits documented by IB and would be rejected by the TWS.
+#[derive(Debug)]
+pub struct InvalidRequestError {
+    reason: String
+}
+
+impl InvalidRequestError {
+    /// Returns the limit that the request violates.
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+impl Error for InvalidRequestError {}
+
+impl fmt::Display for InvalidRequestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid historical data request: {}", self.reason) // user-facing output
+    }
+}
+
+fn invalid(reason: String) -> Result<(), InvalidRequestError> {
+    Err(InvalidRequestError {reason})
+}
+
+/// A chunk of a backfill that could not be loaded.
+#[derive(Debug,Clone)]
+pub struct FailedChunk {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub error: String
+}
+
+/// The result of `backfill_bars`. The series holds the bars of all chunks that were loaded successfully,
+/// failed chunks are reported separately so that they can be retried.
+#[derive(Debug,Clone)]
+pub struct Backfill {
+    pub series: BarSeries,
+    pub failed_chunks: Vec<FailedChunk>
+}
+
+impl Backfill {
+    /// Returns `true` if all chunks were loaded.
+    pub fn is_complete(&self) -> bool {
+        self.failed_chunks.is_empty()
+    }
+}
+
+/// Checks a historical bar request against the limits documented by IB: the duration must be positive, cover at
+/// least one bar and not exceed the maximum duration for the bar size, and the data type must be available for
+/// the security type. `what_to_show` is `None` for dividend adjusted data. Checks depending on the security type are
+/// skipped if it is not set on the contract.
+pub fn validate_bars(sec_type: Option<&SecType>, duration: &HistoricalDataDuration, bar_size: &HistoricalDataBarSize,
+    what_to_show: Option<&HistoricalDataType>) -> Result<(), InvalidRequestError> {
+    validate_duration(duration, bar_size)?;
+    if let Some(HistoricalDataType::Schedule) = what_to_show {
+        return invalid("trading schedules are requested with req_trading_schedule".to_string());
+    }
+    let sec_type = match sec_type {
+        Some(sec_type) => sec_type,
+        None => return Ok(())
+    };
+    let what_to_show = match what_to_show {
+        Some(what_to_show) => what_to_show,
+        None => return match sec_type {
+            SecType::Stock => Ok(()),
+            _ => invalid(format!("adjusted data is only available for stocks, not for {:?}", sec_type))
+        }
+    };
+    let valid = match what_to_show {
+        HistoricalDataType::Trades => !matches!(sec_type, SecType::Forex | SecType::Commodity),
+        HistoricalDataType::Midpoint | HistoricalDataType::Bid | HistoricalDataType::Ask | HistoricalDataType::BidAsk =>
+            !matches!(sec_type, SecType::Index),
+        HistoricalDataType::HistoricalVolatility | HistoricalDataType::OptionImpliedVolatility =>
+            matches!(sec_type, SecType::Stock | SecType::Index),
+        HistoricalDataType::RebateRate | HistoricalDataType::FeeRate => matches!(sec_type, SecType::Stock),
+        HistoricalDataType::YieldBid | HistoricalDataType::YieldAsk | HistoricalDataType::YieldBidAsk | HistoricalDataType::YieldLast =>
+            matches!(sec_type, SecType::Bond),
+        HistoricalDataType::Schedule => true
+    };
+    if !valid {
+        return invalid(format!("{:?} data is not available for {:?} contracts", what_to_show, sec_type));
+    }
+    Ok(())
+}
+
+/// Validates a backfill, which requests chunks with end dates in the past.
+pub(crate) fn validate_backfill(sec_type: Option<&SecType>) -> Result<(), InvalidRequestError> {
+    match sec_type {
+        Some(SecType::ContinuousFuture) =>
+            invalid("continuous futures only support requests ending now, backfill the contracts of a futures chain".to_string()),
+        _ => Ok(())
+    }
+}
+
+/// Checks that the duration is positive, covers at least one bar and does not exceed the maximum duration for the bar size.
+pub fn validate_duration(duration: &HistoricalDataDuration, bar_size: &HistoricalDataBarSize) -> Result<(), InvalidRequestError> {
+    use HistoricalDataDuration::*;
+    let count = match duration {
+        Seconds(count) | Days(count) | Weeks(count) | Months(count) | Years(count) => *count
+    };
+    if count <= 0 {
+        return invalid(format!("duration {:?} must be positive", duration));
+    }
+    if let Seconds(secs) = duration {
+        if *secs > 86400 {
+            return invalid(format!("durations in seconds are limited to 86400 S, use days instead of {} S", secs));
+        }
+    }
+    if duration.max_span() < bar_size.span() {
+        return invalid(format!("duration {:?} is shorter than a single {:?} bar", duration, bar_size));
+    }
+    let max_duration = bar_size.max_duration();
+    if duration.min_span() > max_duration.max_span() {
+        return invalid(format!("duration {:?} exceeds the maximum of {:?} for {:?} bars, use backfill_bars for longer ranges",
+            duration, max_duration, bar_size));
+    }
+    Ok(())
+}
+
+/// Checks a historical bar request with keepUpToDate enabled. In addition to the checks of `validate_bars`, streaming
+/// updates require bars of at least 5 seconds.
+pub fn validate_subscription(sec_type: Option<&SecType>, duration: &HistoricalDataDuration, bar_size: &HistoricalDataBarSize,
+    what_to_show: &HistoricalDataType) -> Result<(), InvalidRequestError> {
+    if let HistoricalDataBarSize::OneSec = bar_size {
+        return invalid("keepUpToDate requires a bar size of at least 5 seconds".to_string());
+    }
+    validate_bars(sec_type, duration, bar_size, Some(what_to_show))
+}
+
+/// Splits the interval from `start` to `end` into chunks that can each be loaded with a single historical data
+/// request. Chunks are returned from the most recent to the oldest, each with the duration to request.
+pub(crate) fn chunks(start: &DateTime<Utc>, end: &DateTime<Utc>, bar_size: &HistoricalDataBarSize)
+    -> Vec<(DateTime<Utc>, DateTime<Utc>, HistoricalDataDuration)> {
+    let max_span = bar_size.max_duration().min_span();
+    let intraday = bar_size.span() < chrono::Duration::days(1);
+    let mut chunks = Vec::new();
+    let mut chunk_end = *end;
+    while chunk_end > *start {
+        let chunk_start = std::cmp::max(*start, chunk_end - max_span);
+        //a chunk must cover at least one bar
+        let secs = std::cmp::max((chunk_end - chunk_start).num_seconds(), bar_size.span().num_seconds());
+        let duration = if intraday && secs <= 86400 {
+            HistoricalDataDuration::Seconds(secs as i32)
+        }
+        else {
+            HistoricalDataDuration::Days(((secs + 86399) / 86400) as i32)
+        };
+        chunks.push((chunk_start, chunk_end, duration));
+        chunk_end = chunk_start;
+    }
+    chunks
+}
+
+/// Client-side pacing of historical data requests. IB rejects more than 60 historical data requests within
+/// 10 minutes and more than 6 requests within 2 seconds, so requests are delayed to stay within these limits.
+pub(crate) struct Pacer {
+    sent: VecDeque<Instant>
+}
+
+impl Pacer {
+    const MAX_REQUESTS: usize = 60;
+    const WINDOW: Duration = Duration::from_secs(600);
+    const MAX_BURST: usize = 5;
+    const BURST_WINDOW: Duration = Duration::from_secs(2);
+
+    pub(crate) fn new() -> Self {
+        Pacer {sent: VecDeque::new()}
+    }
+    /// Returns the number of requests sent within the pacing window.
+    pub(crate) fn requests_in_window(&self) -> usize {
+        let now = Instant::now();
+        self.sent.iter().filter(|t| now.duration_since(**t) < Pacer::WINDOW).count()
+    }
+    /// Waits until another request can be sent without violating the pacing limits and records it.
+    pub(crate) async fn pace(&mut self) {
+        loop {
+            let now = Instant::now();
+            while let Some(t) = self.sent.front() {
+                if now.duration_since(*t) >= Pacer::WINDOW {self.sent.pop_front();}
+                else {break}
+            }
+            if self.sent.len() >= Pacer::MAX_REQUESTS {
+                time::sleep_until(self.sent[0] + Pacer::WINDOW).await;
+                continue;
+            }
+            let burst: Vec<&Instant> = self.sent.iter().rev().take(Pacer::MAX_BURST).collect();
+            if burst.len() == Pacer::MAX_BURST {
+                let oldest = *burst[Pacer::MAX_BURST - 1];
+                if now.duration_since(oldest) < Pacer::BURST_WINDOW {
+                    time::sleep_until(oldest + Pacer::BURST_WINDOW).await;
+                    continue;
+                }
+            }
+            self.sent.push_back(now);
+            return;
+        }
+    }
+}
+
+/// Identifies a stored bar series.
+#[derive(Debug,Clone)]
+pub(crate) struct SeriesKey {
+    pub con_id: i32,
+    pub bar_size: HistoricalDataBarSize,
+    pub what_to_show: HistoricalDataType,
+    pub use_rth: bool
+}
+
+/// Bars loaded from a `HistoricalStore` together with the time ranges that were already requested from IB.
+#[derive(Debug,Clone,Default)]
+pub(crate) struct StoredBars {
+    pub coverage: Vec<(DateTime<Utc>, DateTime<Utc>)>,
+    pub bars: Vec<Bar>
+}
+
+impl StoredBars {
+    /// Returns the parts of the interval from `start` to `end` that are not covered yet.
+    pub(crate) fn missing(&self, start: &DateTime<Utc>, end: &DateTime<Utc>) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        let mut missing = Vec::new();
+        let mut cursor = *start;
+        for (covered_start, covered_end) in &self.coverage {
+            if *covered_end <= cursor {continue}
+            if *covered_start >= *end {break}
+            if *covered_start > cursor {
+                missing.push((cursor, *covered_start));
+            }
+            cursor = *covered_end;
+        }
+        if cursor < *end {
+            missing.push((cursor, *end));
+        }
+        missing
+    }
+    /// Marks the interval from `start` to `end` as covered, merging overlapping and adjacent ranges.
+    pub(crate) fn cover(&mut self, start: DateTime<Utc>, end: DateTime<Utc>) {
+        if start >= end {return}
+        self.coverage.push((start, end));
+        self.coverage.sort();
+        let mut merged: Vec<(DateTime<Utc>, DateTime<Utc>)> = Vec::with_capacity(self.coverage.len());
+        for (range_start, range_end) in self.coverage.drain(..) {
+            match merged.last_mut() {
+                Some(last) if range_start <= last.1 => last.1 = std::cmp::max(last.1, range_end),
+                _ => merged.push((range_start, range_end))
+            }
+        }
+        self.coverage = merged;
+    }
+    /// Removes the bar that is still in progress at `now` and all later bars, and truncates the coverage so that
+    /// the range is requested again once the bar has closed.
+    pub(crate) fn invalidate_open_bar(&mut self, bar_size: &HistoricalDataBarSize, now: &DateTime<Utc>) {
+        let closed_until = *now - bar_size.span();
+        self.bars.retain(|bar| bar.t_stamp <= closed_until);
+        self.coverage.retain(|(start, _)| *start < closed_until);
+        for (_, end) in self.coverage.iter_mut() {
+            if *end > closed_until {*end = closed_until}
+        }
+    }
+}
+
+/// A local on-disk cache for historical bars. Bars are stored in one file per contract ID, bar size, data type and
+/// trading hours setting, together with the time ranges that were already requested. When a store is set on the
+/// client, `req_historical_data` and `backfill_bars` only request the ranges that are missing in the store and persist
+/// the merged series.
+/// The bar that is still in progress is never stored.
+pub struct HistoricalStore {
+    dir: PathBuf
+}
+
+impl HistoricalStore {
+    /// Opens a store in the given directory, which is created if it does not exist.
+    pub fn open<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        fs::create_dir_all(dir.as_ref())?;
+        Ok(HistoricalStore {dir: dir.as_ref().to_path_buf()})
+    }
+    /// Deletes the stored bars of a contract for the given bar size, data type and trading hours setting.
+    pub fn invalidate(&self, con_id: i32, bar_size: HistoricalDataBarSize, what_to_show: HistoricalDataType, use_rth: bool) -> io::Result<()> {
+        let path = self.path(&SeriesKey {con_id, bar_size, what_to_show, use_rth});
+        match fs::remove_file(path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(())
+        }
+    }
+
+    fn path(&self, key: &SeriesKey) -> PathBuf {
+        let hours = if key.use_rth {"rth"} else {"all"};
+        self.dir.join(format!("{}_{:?}_{:?}_{}.bars", key.con_id, key.bar_size, key.what_to_show, hours))
+    }
+    /// Loads the stored bars, localized in the given time zone. Returns empty bars if nothing is stored yet.
+    pub(crate) fn load(&self, key: &SeriesKey, tz: &Tz) -> io::Result<StoredBars> {
+        let file = match fs::File::open(self.path(key)) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(StoredBars::default()),
+            Err(err) => return Err(err)
+        };
+        let mut stored = StoredBars::default();
+        for line in io::BufReader::new(file).lines() {
+            let line = line?;
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let invalid_line = || io::Error::new(io::ErrorKind::InvalidData, format!("invalid line in bar store: {}", line));
+            if fields.first() == Some(&"#coverage") && fields.len() == 3 {
+                let start = parse_time(fields[1], &Utc).ok_or_else(invalid_line)?;
+                let end = parse_time(fields[2], &Utc).ok_or_else(invalid_line)?;
+                stored.coverage.push((start, end));
+            }
+            else {
+                stored.bars.push(parse_bar(&fields, tz).ok_or_else(invalid_line)?);
+            }
+        }
+        Ok(stored)
+    }
+    /// Replaces the stored bars.
+    pub(crate) fn save(&self, key: &SeriesKey, stored: &StoredBars) -> io::Result<()> {
+        let path = self.path(key);
+        let tmp_path = path.with_extension("tmp");
+        let mut writer = BufWriter::new(fs::File::create(&tmp_path)?);
+        for (start, end) in &stored.coverage {
+            writeln!(writer, "#coverage {} {}", start.timestamp(), end.timestamp())?;
+        }
+        for bar in &stored.bars {
+            writeln!(writer, "{} {} {} {} {} {} {} {}", bar.t_stamp.timestamp(), bar.open, bar.high, bar.low, bar.close,
+                bar.wap, bar.volume, bar.count)?;
+        }
+        writer.flush()?;
+        fs::rename(tmp_path, path)
+    }
+}
+
+fn parse_time<T: TimeZone>(val: &str, tz: &T) -> Option<DateTime<T>> {
+    tz.timestamp_opt(val.parse().ok()?, 0).single()
+}
+
+fn parse_bar(fields: &[&str], tz: &Tz) -> Option<Bar> {
+    if fields.len() != 8 {return None}
+    Some(Bar {
+        t_stamp: parse_time(fields[0], tz)?,
+        open: fields[1].parse().ok()?,
+        high: fields[2].parse().ok()?,
+        low: fields[3].parse().ok()?,
+        close: fields[4].parse().ok()?,
+        wap: fields[5].parse::<Decimal>().ok()?,
+        volume: fields[6].parse::<Decimal>().ok()?,
+        count: fields[7].parse().ok()?
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use HistoricalDataBarSize::*;
+
+    const BAR_SIZES: [HistoricalDataBarSize; 21] = [OneSec, FiveSecs, TenSecs, FifteenSecs, ThirtySecs, OneMin, TwoMins,
+        ThreeMins, FiveMins, TenMins, FifteenMins, TwentyMins, ThirtyMins, OneHour, TwoHours, ThreeHours, FourHours,
+        EightHours, OneDay, OneWeek, OneMonth];
+
+    fn time(secs: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(1677628800 + secs, 0).unwrap()
+    }
+
+    /// Checks that the chunks cover the interval without gaps or overlaps, newest first, and that each chunk can be
+    /// requested with its duration.
+    fn check_chunks(start: &DateTime<Utc>, end: &DateTime<Utc>, bar_size: &HistoricalDataBarSize)
+        -> Vec<(DateTime<Utc>, DateTime<Utc>, HistoricalDataDuration)> {
+        let chunks = chunks(start, end, bar_size);
+        assert_eq!(chunks.first().map(|chunk| chunk.1), Some(*end), "{:?}", bar_size);
+        assert_eq!(chunks.last().map(|chunk| chunk.0), Some(*start), "{:?}", bar_size);
+        for pair in chunks.windows(2) {
+            assert_eq!(pair[0].0, pair[1].1, "{:?}", bar_size);
+        }
+        for (chunk_start, chunk_end, duration) in &chunks {
+            assert!(chunk_start < chunk_end);
+            assert!(*chunk_end - *chunk_start <= bar_size.max_duration().min_span(), "{:?}", bar_size);
+            assert!(duration.min_span() >= *chunk_end - *chunk_start, "{:?} {:?}", bar_size, duration);
+            assert!(validate_duration(duration, bar_size).is_ok(), "{:?} {:?}", bar_size, duration);
+        }
+        chunks
+    }
+
+    #[test]
+    fn chunks_exact_multiple() {
+        let chunks = check_chunks(&time(0), &time(3 * 86400), &OneMin);
+        let expected: Vec<_> = (0..3).rev().map(|day| (time(day * 86400), time((day + 1) * 86400), HistoricalDataDuration::Seconds(86400))).collect();
+        assert_eq!(chunks, expected);
+        for bar_size in BAR_SIZES {
+            let max_span = bar_size.max_duration().min_span();
+            assert_eq!(check_chunks(&time(0), &(time(0) + max_span * 3), &bar_size).len(), 3, "{:?}", bar_size);
+        }
+    }
+
+    #[test]
+    fn chunks_remainder() {
+        let chunks = check_chunks(&time(0), &time(5 * 43200), &OneMin);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[2], (time(0), time(43200), HistoricalDataDuration::Seconds(43200)));
+        let chunks = check_chunks(&time(0), &time(400 * 86400), &OneDay);
+        assert_eq!(chunks[1], (time(0), time(35 * 86400), HistoricalDataDuration::Days(35)));
+        for bar_size in BAR_SIZES {
+            let max_span = bar_size.max_duration().min_span();
+            assert_eq!(check_chunks(&time(0), &(time(0) + max_span * 2 + max_span / 2), &bar_size).len(), 3, "{:?}", bar_size);
+        }
+    }
+
+    #[test]
+    fn chunks_shorter_than_a_bar() {
+        //a chunk requests at least one bar
+        assert_eq!(chunks(&time(0), &time(7200), &OneDay), vec![(time(0), time(7200), HistoricalDataDuration::Days(1))]);
+        assert_eq!(chunks(&time(0), &time(20), &ThirtySecs), vec![(time(0), time(20), HistoricalDataDuration::Seconds(30))]);
+        assert!(chunks(&time(0), &time(0), &OneMin).is_empty());
+    }
+
+    fn bar(secs: i64, close: f64) -> Bar {
+        Bar {t_stamp: time(secs).with_timezone(&chrono_tz::US::Eastern), open: 100.0, high: 101.0, low: 99.0, close,
+            wap: Decimal::new(10025, 2), volume: Decimal::new(1, 1), count: 7}
+    }
+
+    fn store(name: &str) -> HistoricalStore {
+        let dir = std::env::temp_dir().join(format!("rs_ib_api_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        HistoricalStore::open(dir).unwrap()
+    }
+
+    const KEY: SeriesKey = SeriesKey {con_id: 265598, bar_size: OneMin, what_to_show: HistoricalDataType::Trades, use_rth: true};
+
+    #[test]
+    fn cover_merges_ranges() {
+        let mut stored = StoredBars::default();
+        stored.cover(time(600), time(900));
+        stored.cover(time(0), time(300));
+        assert_eq!(stored.coverage, vec![(time(0), time(300)), (time(600), time(900))]);
+        //adjacent
+        stored.cover(time(300), time(400));
+        assert_eq!(stored.coverage, vec![(time(0), time(400)), (time(600), time(900))]);
+        //overlapping both ranges
+        stored.cover(time(350), time(700));
+        assert_eq!(stored.coverage, vec![(time(0), time(900))]);
+        //contained and empty ranges change nothing
+        stored.cover(time(100), time(200));
+        stored.cover(time(1000), time(1000));
+        assert_eq!(stored.coverage, vec![(time(0), time(900))]);
+    }
+
+    #[test]
+    fn missing_returns_holes() {
+        let mut stored = StoredBars::default();
+        assert_eq!(stored.missing(&time(0), &time(100)), vec![(time(0), time(100))]);
+        stored.cover(time(100), time(200));
+        stored.cover(time(300), time(400));
+        assert_eq!(stored.missing(&time(0), &time(500)),
+            vec![(time(0), time(100)), (time(200), time(300)), (time(400), time(500))]);
+        assert_eq!(stored.missing(&time(150), &time(350)), vec![(time(200), time(300))]);
+        assert!(stored.missing(&time(120), &time(180)).is_empty());
+        assert_eq!(stored.missing(&time(450), &time(500)), vec![(time(450), time(500))]);
+    }
+
+    #[test]
+    fn invalidate_open_bar_drops_last_bar() {
+        let mut stored = StoredBars {coverage: vec![(time(0), time(180))], bars: vec![bar(0, 1.0), bar(60, 2.0), bar(120, 3.0)]};
+        //at 150 s the bar starting at 120 s is still in progress
+        stored.invalidate_open_bar(&OneMin, &time(150));
+        assert_eq!(stored.bars.iter().map(|bar| bar.close).collect::<Vec<_>>(), vec![1.0, 2.0]);
+        assert_eq!(stored.coverage, vec![(time(0), time(90))]);
+        assert_eq!(stored.missing(&time(0), &time(180)), vec![(time(90), time(180))]);
+        //ranges starting after the last closed bar are dropped
+        let mut stored = StoredBars {coverage: vec![(time(0), time(60)), (time(120), time(180))], bars: vec![]};
+        stored.invalidate_open_bar(&OneMin, &time(150));
+        assert_eq!(stored.coverage, vec![(time(0), time(60))]);
+    }
+
+    #[test]
+    fn store_round_trip() {
+        let store = store("round_trip");
+        let tz = chrono_tz::US::Eastern;
+        assert!(store.load(&KEY, &tz).unwrap().bars.is_empty());
+        let stored = StoredBars {coverage: vec![(time(0), time(120)), (time(600), time(660))], bars: vec![bar(0, 100.5), bar(60, 0.1)]};
+        store.save(&KEY, &stored).unwrap();
+        let loaded = store.load(&KEY, &tz).unwrap();
+        assert_eq!(loaded.coverage, stored.coverage);
+        assert_eq!(loaded.bars.len(), 2);
+        assert_eq!(loaded.bars[1].t_stamp, stored.bars[1].t_stamp);
+        assert_eq!(loaded.bars[1].t_stamp.timezone(), tz);
+        assert_eq!((loaded.bars[1].close, loaded.bars[1].wap, loaded.bars[1].volume, loaded.bars[1].count), (0.1, Decimal::new(10025, 2), Decimal::new(1, 1), 7));
+        store.invalidate(KEY.con_id, KEY.bar_size, KEY.what_to_show, KEY.use_rth).unwrap();
+        assert!(store.load(&KEY, &tz).unwrap().coverage.is_empty());
+        fs::remove_dir_all(&store.dir).unwrap();
+    }
+
+    #[test]
+    fn corrupt_store_is_an_error() {
+        let store = store("corrupt");
+        let tz = chrono_tz::US::Eastern;
+        let stored = StoredBars {coverage: vec![(time(0), time(120))], bars: vec![bar(0, 100.5), bar(60, 101.0)]};
+        store.save(&KEY, &stored).unwrap();
+        let contents = fs::read_to_string(store.path(&KEY)).unwrap();
+        //truncated in the middle of the last bar
+        fs::write(store.path(&KEY), &contents[..contents.len() - 6]).unwrap();
+        assert_eq!(store.load(&KEY, &tz).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        for corrupt in ["#coverage 0\n", "#coverage 0 x\n", "1677628800 100 101 99 100 abc 1 7\n", "\n", "\u{0}\u{ff}garbage"] {
+            fs::write(store.path(&KEY), corrupt).unwrap();
+            assert!(store.load(&KEY, &tz).is_err(), "{:?}", corrupt);
+        }
+        fs::remove_dir_all(&store.dir).unwrap();
+    }
+}
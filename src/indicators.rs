@@ -0,0 +1,329 @@
+use std::collections::VecDeque;
+use chrono::{DateTime, NaiveDate};
+use chrono_tz::Tz;
+use rust_decimal::prelude::*;
+use crate::bars::{Bar, BarSeries};
+
+/// An incrementally computed technical indicator. Indicators are fed closed bars in time order, e.g. a historical
+/// `BarSeries` for warm-up and then live bars. Use `LiveIndicator` for streams that repeatedly update the bar
+/// in progress, like keepUpToDate subscriptions.
+pub trait Indicator {
+    type Output;
+    /// Adds a closed bar and returns the updated value, or `None` while the indicator is warming up.
+    fn update(&mut self, bar: &Bar) -> Option<Self::Output>;
+    /// Returns the current value, or `None` while the indicator is warming up.
+    fn value(&self) -> Option<Self::Output>;
+    /// Adds all bars of the series and returns the resulting value.
+    fn warm_up(&mut self, series: &BarSeries) -> Option<Self::Output> {
+        for bar in &series.data {
+            self.update(bar);
+        }
+        self.value()
+    }
+}
+
+/// Wraps an indicator for streams in which the bar in progress is updated several times before it closes.
+/// Updates with the same time stamp as the previous update replace it instead of adding another bar.
+#[derive(Debug,Clone)]
+pub struct LiveIndicator<I: Indicator + Clone> {
+    committed: I,
+    current: I,
+    t_stamp: Option<DateTime<Tz>>
+}
+
+impl<I: Indicator + Clone> LiveIndicator<I> {
+    pub fn new(indicator: I) -> Self {
+        LiveIndicator {committed: indicator.clone(), current: indicator, t_stamp: None}
+    }
+    /// Adds a new bar or replaces the bar in progress if the time stamp did not change.
+    pub fn update(&mut self, bar: &Bar) -> Option<I::Output> {
+        if self.t_stamp != Some(bar.t_stamp) {
+            self.committed = self.current.clone();
+            self.t_stamp = Some(bar.t_stamp);
+        }
+        self.current = self.committed.clone();
+        self.current.update(bar)
+    }
+    /// Returns the value including the bar in progress.
+    pub fn value(&self) -> Option<I::Output> {
+        self.current.value()
+    }
+    /// Returns the wrapped indicator.
+    pub fn indicator(&self) -> &I {
+        &self.current
+    }
+}
+
+/// Simple moving average of the close.
+#[derive(Debug,Clone)]
+pub struct Sma {
+    period: usize,
+    window: VecDeque<f64>,
+    sum: f64
+}
+
+impl Sma {
+    pub fn new(period: usize) -> Self {
+        Sma {period: period.max(1), window: VecDeque::with_capacity(period), sum: 0.0}
+    }
+    fn add(&mut self, val: f64) -> Option<f64> {
+        self.window.push_back(val);
+        self.sum += val;
+        if self.window.len() > self.period {
+            self.sum -= self.window.pop_front().unwrap_or_default();
+        }
+        self.current()
+    }
+    fn current(&self) -> Option<f64> {
+        if self.window.len() == self.period {Some(self.sum / self.period as f64)}
+        else {None}
+    }
+}
+
+impl Indicator for Sma {
+    type Output = f64;
+    fn update(&mut self, bar: &Bar) -> Option<f64> {
+        self.add(bar.close)
+    }
+    fn value(&self) -> Option<f64> {
+        self.current()
+    }
+}
+
+/// Exponential moving average of the close with smoothing factor 2 / (period + 1), seeded with the simple
+/// moving average of the first `period` closes.
+#[derive(Debug,Clone)]
+pub struct Ema {
+    alpha: f64,
+    seed: Sma,
+    value: Option<f64>
+}
+
+impl Ema {
+    pub fn new(period: usize) -> Self {
+        let period = period.max(1);
+        Ema {alpha: 2.0 / (period as f64 + 1.0), seed: Sma::new(period), value: None}
+    }
+    fn add(&mut self, val: f64) -> Option<f64> {
+        self.value = match self.value {
+            Some(ema) => Some(ema + self.alpha * (val - ema)),
+            None => self.seed.add(val)
+        };
+        self.value
+    }
+}
+
+impl Indicator for Ema {
+    type Output = f64;
+    fn update(&mut self, bar: &Bar) -> Option<f64> {
+        self.add(bar.close)
+    }
+    fn value(&self) -> Option<f64> {
+        self.value
+    }
+}
+
+/// Wilder's smoothing: the first value is the mean of `period` values, then `(prev * (period - 1) + val) / period`.
+#[derive(Debug,Clone)]
+struct Wilder {
+    period: usize,
+    seed: Sma,
+    value: Option<f64>
+}
+
+impl Wilder {
+    fn new(period: usize) -> Self {
+        Wilder {period: period.max(1), seed: Sma::new(period), value: None}
+    }
+    fn add(&mut self, val: f64) -> Option<f64> {
+        self.value = match self.value {
+            Some(prev) => Some((prev * (self.period as f64 - 1.0) + val) / self.period as f64),
+            None => self.seed.add(val)
+        };
+        self.value
+    }
+}
+
+/// Relative strength index of the close (0 to 100) with Wilder's smoothing of gains and losses.
+#[derive(Debug,Clone)]
+pub struct Rsi {
+    prev_close: Option<f64>,
+    gain: Wilder,
+    loss: Wilder
+}
+
+impl Rsi {
+    pub fn new(period: usize) -> Self {
+        Rsi {prev_close: None, gain: Wilder::new(period), loss: Wilder::new(period)}
+    }
+}
+
+impl Indicator for Rsi {
+    type Output = f64;
+    fn update(&mut self, bar: &Bar) -> Option<f64> {
+        if let Some(prev) = self.prev_close.replace(bar.close) {
+            let change = bar.close - prev;
+            self.gain.add(change.max(0.0));
+            self.loss.add((-change).max(0.0));
+        }
+        self.value()
+    }
+    fn value(&self) -> Option<f64> {
+        let (gain, loss) = (self.gain.value?, self.loss.value?);
+        if loss == 0.0 {
+            return Some(if gain == 0.0 {50.0} else {100.0});
+        }
+        Some(100.0 - 100.0 / (1.0 + gain / loss))
+    }
+}
+
+/// Average true range with Wilder's smoothing. The first bar's true range is its high - low.
+#[derive(Debug,Clone)]
+pub struct Atr {
+    prev_close: Option<f64>,
+    average: Wilder
+}
+
+impl Atr {
+    pub fn new(period: usize) -> Self {
+        Atr {prev_close: None, average: Wilder::new(period)}
+    }
+}
+
+impl Indicator for Atr {
+    type Output = f64;
+    fn update(&mut self, bar: &Bar) -> Option<f64> {
+        let true_range = match self.prev_close.replace(bar.close) {
+            Some(prev) => (bar.high - bar.low).max((bar.high - prev).abs()).max((bar.low - prev).abs()),
+            None => bar.high - bar.low
+        };
+        self.average.add(true_range)
+    }
+    fn value(&self) -> Option<f64> {
+        self.average.value
+    }
+}
+
+/// Values of the Bollinger bands.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct BollingerBands {
+    pub lower: f64,
+    pub middle: f64,
+    pub upper: f64
+}
+
+/// Bollinger bands of the close: simple moving average +/- `width` population standard deviations.
+#[derive(Debug,Clone)]
+pub struct Bollinger {
+    sma: Sma,
+    width: f64
+}
+
+impl Bollinger {
+    pub fn new(period: usize, width: f64) -> Self {
+        Bollinger {sma: Sma::new(period), width}
+    }
+}
+
+impl Indicator for Bollinger {
+    type Output = BollingerBands;
+    fn update(&mut self, bar: &Bar) -> Option<BollingerBands> {
+        self.sma.add(bar.close);
+        self.value()
+    }
+    fn value(&self) -> Option<BollingerBands> {
+        let middle = self.sma.current()?;
+        let variance = self.sma.window.iter().map(|val| (val - middle).powi(2)).sum::<f64>() / self.sma.period as f64;
+        let band = self.width * variance.sqrt();
+        Some(BollingerBands {lower: middle - band, middle, upper: middle + band})
+    }
+}
+
+/// Volume weighted average price of the bars' WAPs (typical price (high + low + close) / 3 for bars without a WAP).
+/// The average is reset at the start of each trading day in the time zone of the bars, unless created with `cumulative`.
+#[derive(Debug,Clone)]
+pub struct Vwap {
+    daily: bool,
+    date: Option<NaiveDate>,
+    notional: Decimal,
+    volume: Decimal
+}
+
+impl Vwap {
+    pub fn new() -> Self {
+        Vwap {daily: true, date: None, notional: Decimal::ZERO, volume: Decimal::ZERO}
+    }
+    /// Creates a VWAP that is never reset.
+    pub fn cumulative() -> Self {
+        Vwap {daily: false, ..Vwap::new()}
+    }
+}
+
+impl Default for Vwap {
+    fn default() -> Self {
+        Vwap::new()
+    }
+}
+
+impl Indicator for Vwap {
+    type Output = f64;
+    fn update(&mut self, bar: &Bar) -> Option<f64> {
+        let date = bar.t_stamp.date_naive();
+        if self.daily && self.date != Some(date) {
+            self.notional = Decimal::ZERO;
+            self.volume = Decimal::ZERO;
+        }
+        self.date = Some(date);
+        let price = if bar.wap > Decimal::ZERO {bar.wap}
+            else {Decimal::from_f64((bar.high + bar.low + bar.close) / 3.0).unwrap_or_default()};
+        self.notional += price * bar.volume;
+        self.volume += bar.volume;
+        self.value()
+    }
+    fn value(&self) -> Option<f64> {
+        if self.volume > Decimal::ZERO {(self.notional / self.volume).to_f64()}
+        else {None}
+    }
+}
+
+/// Values of the MACD.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct MacdValue {
+    pub macd: f64,
+    pub signal: f64,
+    pub histogram: f64
+}
+
+/// Moving average convergence divergence: the difference of a fast and a slow EMA of the close, its signal line
+/// (an EMA of the MACD) and the histogram (MACD - signal). Commonly used with periods 12, 26 and 9.
+#[derive(Debug,Clone)]
+pub struct Macd {
+    fast: Ema,
+    slow: Ema,
+    signal: Ema,
+    value: Option<MacdValue>
+}
+
+impl Macd {
+    pub fn new(fast: usize, slow: usize, signal: usize) -> Self {
+        Macd {fast: Ema::new(fast), slow: Ema::new(slow), signal: Ema::new(signal), value: None}
+    }
+}
+
+impl Indicator for Macd {
+    type Output = MacdValue;
+    fn update(&mut self, bar: &Bar) -> Option<MacdValue> {
+        let fast = self.fast.add(bar.close);
+        let slow = self.slow.add(bar.close);
+        if let (Some(fast), Some(slow)) = (fast, slow) {
+            let macd = fast - slow;
+            if let Some(signal) = self.signal.add(macd) {
+                self.value = Some(MacdValue {macd, signal, histogram: macd - signal});
+            }
+        }
+        self.value
+    }
+    fn value(&self) -> Option<MacdValue> {
+        self.value
+    }
+}
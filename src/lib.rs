@@ -41,11 +41,21 @@
 //! ```
 pub mod enums;
 pub mod opt_params;
+pub mod futures_chain;
+pub mod strategy;
 mod utils;
 pub mod client;
-mod account;
+pub mod account;
 mod frame;
 pub mod contract;
 pub mod order;
 pub mod ticker;
-pub mod bars;
\ No newline at end of file
+pub mod bars;
+#[cfg(any(feature = "csv", feature = "serde", feature = "arrow"))]
+mod bar_io;
+mod resample;
+pub mod indicators;
+pub mod calendar;
+pub mod historical;
+pub mod ticks;
+pub mod market_rule;
\ No newline at end of file
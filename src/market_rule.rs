@@ -0,0 +1,55 @@
+use rust_decimal::prelude::*;
+
+/// A price increment that applies to all prices from `low_edge` up to the low edge of the next increment.
+#[derive(Debug,Clone)]
+pub struct PriceIncrement {
+    pub low_edge: Decimal,
+    pub increment: Decimal
+}
+
+/// A market rule defines the valid price increments of a contract on an exchange depending on the price level.
+/// Market rule IDs of a contract are listed in its `ContractDetails` for each valid exchange.
+#[derive(Debug,Clone)]
+pub struct MarketRule {
+    pub id: i32,
+    pub increments: Vec<PriceIncrement>
+}
+
+impl MarketRule {
+    /// Creates a rule with a constant increment, e.g. from the minimum tick of a contract.
+    pub fn from_min_tick(min_tick: Decimal) -> Self {
+        MarketRule {
+            id: -1,
+            increments: vec![PriceIncrement {low_edge: Decimal::ZERO, increment: min_tick}]
+        }
+    }
+    /// Returns the price increment that applies at the given price level.
+    pub fn increment(&self, price: Decimal) -> Option<Decimal> {
+        let level = price.abs();
+        self.increments.iter()
+            .filter(|inc| inc.low_edge <= level)
+            .max_by(|a, b| a.low_edge.cmp(&b.low_edge))
+            .or_else(|| self.increments.first())
+            .map(|inc| inc.increment)
+            .filter(|inc| *inc > Decimal::ZERO)
+    }
+    /// Rounds the price to the nearest valid increment.
+    pub fn round(&self, price: Decimal) -> Decimal {
+        self.round_with(price, |ticks| ticks.round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero))
+    }
+    /// Rounds the price down to the next valid increment.
+    pub fn round_down(&self, price: Decimal) -> Decimal {
+        self.round_with(price, |ticks| ticks.floor())
+    }
+    /// Rounds the price up to the next valid increment.
+    pub fn round_up(&self, price: Decimal) -> Decimal {
+        self.round_with(price, |ticks| ticks.ceil())
+    }
+
+    fn round_with(&self, price: Decimal, round_ticks: impl Fn(Decimal) -> Decimal) -> Decimal {
+        match self.increment(price) {
+            Some(increment) => (round_ticks(price / increment) * increment).normalize(),
+            None => price
+        }
+    }
+}
@@ -1,6 +1,12 @@
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
+use chrono::NaiveDate;
 use rust_decimal::Decimal;
+use crate::contract::Contract;
+use crate::enums::{OptionRight, SecType};
 
+/// The option parameters of an underlying on one exchange for one trading class.
+#[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OptParams {
     pub underlying_con_id: Option<String>,
     pub exchange: Option<String>,
@@ -8,4 +14,104 @@ pub struct OptParams {
     pub multiplier: Option<String>,
     pub strikes: HashSet<Decimal>,
     pub expirations: HashSet<String>
-}
\ No newline at end of file
+}
+
+/// The option chain of an underlying as returned by `IBClient::req_option_chain`, a grid of expiry × strike × right
+/// for each trading class. The filters narrow the grid down before option contracts are created with `contracts`.
+/// Strikes are listed per trading class, not every strike exists for every expiry.
+#[derive(Debug,Clone)]
+pub struct OptionChain {
+    pub underlying: Contract,
+    pub params: Vec<OptParams>
+}
+
+fn parse_expiry(expiry: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(expiry, "%Y%m%d").ok()
+}
+
+impl OptionChain {
+    pub fn new(underlying: Contract, params: Vec<OptParams>) -> Self {
+        OptionChain {underlying, params}
+    }
+    /// Returns the parameters used for the grid: the SMART routed entries if there are any (stock and index options),
+    /// otherwise the entries of all exchanges (e.g. futures options).
+    fn routed_params(&self) -> impl Iterator<Item = &OptParams> {
+        let smart = self.params.iter().any(|params| params.exchange.as_deref() == Some("SMART"));
+        self.params.iter().filter(move |params| !smart || params.exchange.as_deref() == Some("SMART"))
+    }
+    /// Returns all expirations, sorted.
+    pub fn expirations(&self) -> Vec<String> {
+        self.routed_params().flat_map(|params| params.expirations.iter().cloned()).collect::<BTreeSet<_>>().into_iter().collect()
+    }
+    /// Returns all strikes, sorted.
+    pub fn strikes(&self) -> Vec<Decimal> {
+        self.routed_params().flat_map(|params| params.strikes.iter().copied()).collect::<BTreeSet<_>>().into_iter().collect()
+    }
+    /// Keeps the expirations with `min_days` to `max_days` days to expiry as of the given date.
+    pub fn dte_range(mut self, as_of: NaiveDate, min_days: i64, max_days: i64) -> Self {
+        for params in &mut self.params {
+            params.expirations.retain(|expiry| parse_expiry(expiry)
+                .is_some_and(|date| (min_days..=max_days).contains(&(date - as_of).num_days())));
+        }
+        self
+    }
+    /// Keeps the strikes whose ratio to the spot price is between `min` and `max`, e.g. 0.9 and 1.1 for strikes
+    /// within 10% of the spot price.
+    pub fn moneyness(mut self, spot: Decimal, min: Decimal, max: Decimal) -> Self {
+        if spot > Decimal::ZERO {
+            for params in &mut self.params {
+                params.strikes.retain(|strike| (min..=max).contains(&(strike / spot)));
+            }
+        }
+        self
+    }
+    /// Keeps the `count` strikes closest to the spot price (at the money) of each trading class.
+    pub fn strikes_around(mut self, spot: Decimal, count: usize) -> Self {
+        for params in &mut self.params {
+            let mut strikes: Vec<Decimal> = params.strikes.iter().copied().collect();
+            strikes.sort_by_key(|strike| ((strike - spot).abs(), *strike));
+            params.strikes = strikes.into_iter().take(count).collect();
+        }
+        self
+    }
+    /// Returns the option contract of the chain with the given expiry, strike and right, which is not qualified yet.
+    /// Returns `None` if the expiry or strike is not part of the chain.
+    pub fn contract(&self, expiry: &str, strike: Decimal, right: OptionRight) -> Option<Contract> {
+        let params = self.routed_params().find(|params| params.expirations.contains(expiry) && params.strikes.contains(&strike))?;
+        Some(self.option(params, expiry, strike, right))
+    }
+    /// Returns the option contracts of the grid, calls and puts for each expiry and strike of each trading class,
+    /// ordered by expiry and strike. The contracts are not qualified, use `IBClient::qualify_option_chain`.
+    pub fn contracts(&self) -> Vec<Contract> {
+        let mut contracts = Vec::new();
+        for params in self.routed_params() {
+            let expirations: BTreeSet<&String> = params.expirations.iter().collect();
+            let strikes: BTreeSet<&Decimal> = params.strikes.iter().collect();
+            for expiry in &expirations {
+                for strike in &strikes {
+                    contracts.push(self.option(params, expiry, **strike, OptionRight::Call));
+                    contracts.push(self.option(params, expiry, **strike, OptionRight::Put));
+                }
+            }
+        }
+        contracts
+    }
+    fn option(&self, params: &OptParams, expiry: &str, strike: Decimal, right: OptionRight) -> Contract {
+        let sec_type = match self.underlying.sec_type {
+            Some(SecType::Future) => SecType::OptionOnFuture,
+            _ => SecType::Option
+        };
+        Contract {
+            symbol: self.underlying.symbol.clone(),
+            sec_type: Some(sec_type),
+            last_trade_date_or_contract_month: Some(expiry.to_string()),
+            strike: Some(strike),
+            right: Some(right),
+            multiplier: params.multiplier.clone(),
+            exchange: params.exchange.clone(),
+            currency: self.underlying.currency.clone(),
+            trading_class: params.trading_class.clone(),
+            ..Default::default()
+        }
+    }
+}
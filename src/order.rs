@@ -3,6 +3,10 @@ use crate::enums::constants::COMPETE_AGAINST_BEST_OFFSET_UP_TO_MID;
 use rust_decimal::prelude::*;
 use crate::utils::ib_message::Encodable;
 use crate::contract::Contract;
+use crate::market_rule::MarketRule;
+use crate::bars::parse_tws_time;
+use chrono::DateTime;
+use chrono_tz::Tz;
 use crossbeam::channel;
 use tokio::sync::watch;
 
@@ -103,6 +107,28 @@ impl OrderTracker {
             None => None
         }
     }
+    ///Returns the order as last updated by the TWS.
+    pub fn order(&self) -> Order {
+        self.order_rx.borrow().clone()
+    }
+    ///Returns the order state including the margin impact and commissions.
+    pub fn order_state(&self) -> OrderState {
+        self.order_state_rx.borrow().clone()
+    }
+    ///Returns the last order status, if any was received.
+    pub fn order_status(&self) -> Option<OrderStatus> {
+        self.order_status_rx.borrow().clone()
+    }
+    ///Returns the executions of the order received so far.
+    pub fn executions(&mut self) -> &[Execution] {
+        self.update_exec();
+        &self.executions
+    }
+    ///Returns the commission reports of the executions received so far.
+    pub fn commission_reports(&mut self) -> &[CommissionReport] {
+        self.update_com();
+        &self.commission_reports
+    }
     ///Returns total commissions paid.
     pub fn commissions_paid(&mut self) -> Option<Decimal> {
         self.update_com();
@@ -116,6 +142,7 @@ impl OrderTracker {
 }
 
 #[derive(Default,Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct SoftDollarTier {
     pub(crate) name: Option<String>,
     pub(crate) val: Option<String>,
@@ -126,6 +153,7 @@ pub(crate) struct SoftDollarTier {
 /// factory functions ensuring that the created orders are in a valid state accepted by the TWS API.
 /// Only basic order types are currently supported.
 #[derive(Default,Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Order {
 
     //contract
@@ -343,10 +371,23 @@ impl Order {
         order
     }
     /// Sets the necessary parameters to submit a Combo order.
-    pub fn combo(mut self) -> Self {
-        self.smart_combo_routing_params = Some(vec![("NonGuaranteed".to_string(), "1".to_string())]);
+    pub fn combo(self) -> Self {
+        self.smart_combo_routing_param("NonGuaranteed", "1")
+    }
+    /// Sets a SMART combo routing parameter, e.g. `LeginPrio` to leg into a combo in a given order. An existing value
+    /// of the parameter is replaced.
+    pub fn smart_combo_routing_param(mut self, tag: &str, value: &str) -> Self {
+        let params = self.smart_combo_routing_params.get_or_insert_with(Vec::new);
+        match params.iter_mut().find(|(existing, _)| existing == tag) {
+            Some(param) => param.1 = value.to_string(),
+            None => params.push((tag.to_string(), value.to_string()))
+        }
         self
     }
+    /// Returns the SMART combo routing parameters as pairs of tag and value.
+    pub fn smart_combo_routing_params(&self) -> &[(String, String)] {
+        self.smart_combo_routing_params.as_deref().unwrap_or_default()
+    }
     /// Creates a market-on-close order. Only possible for stocks that trade on NYSE or NASDAQ.
     pub fn market_on_close(contract: Contract, action: Action, qty: Decimal) -> Self {
         let mut order = Order::new();
@@ -376,6 +417,48 @@ impl Order {
         order.tif = Some(tif);
         order
     }
+    /// Rounds the limit and auxiliary prices of the order to the nearest valid increment of the market rule.
+    pub fn round_prices(mut self, rule: &MarketRule) -> Self {
+        self.lmt_price = self.lmt_price.map(|price| rule.round(price));
+        self.aux_price = self.aux_price.map(|price| rule.round(price));
+        self
+    }
+    /// Returns the contract of the order.
+    pub fn contract(&self) -> &Contract {
+        &self.contract
+    }
+    /// Returns the order ID, which is assigned when the order is placed.
+    pub fn order_id(&self) -> i32 {
+        self.order_id
+    }
+    /// Returns the permanent ID assigned by the TWS.
+    pub fn perm_id(&self) -> Option<i32> {
+        self.perm_id
+    }
+    /// Returns whether the order buys or sells.
+    pub fn action(&self) -> Option<&Action> {
+        self.action.as_ref()
+    }
+    /// Returns the total quantity of the order.
+    pub fn total_qty(&self) -> Option<Decimal> {
+        self.total_qty
+    }
+    /// Returns the order type.
+    pub fn order_type(&self) -> Option<&OrderType> {
+        self.order_type.as_ref()
+    }
+    /// Returns the limit price.
+    pub fn lmt_price(&self) -> Option<Decimal> {
+        self.lmt_price
+    }
+    /// Returns the auxiliary price, e.g. the stop price of stop orders.
+    pub fn aux_price(&self) -> Option<Decimal> {
+        self.aux_price
+    }
+    /// Returns the time in force.
+    pub fn tif(&self) -> Option<&TimeInForce> {
+        self.tif.as_ref()
+    }
 }
 
 impl Encodable for Order {
@@ -614,68 +697,300 @@ impl Encodable for Order {
     }
 }  
 
+/// The state of an order with its margin impact and commissions, as sent with open orders.
 #[derive(Default,Debug,Clone)]
-pub(crate) struct OrderState {
-    pub status: Option<String>,
-    pub init_margin_before: Option<Decimal>,
-    pub maint_margin_before: Option<Decimal>,
-    pub init_margin_change: Option<Decimal>,
-    pub equity_with_loan_value_before: Option<Decimal>,
-    pub maint_margin_change: Option<Decimal>,
-    pub equity_with_loan_change: Option<Decimal>,
-    pub init_margin_after: Option<Decimal>,
-    pub maint_margin_after: Option<Decimal>,
-    pub equity_with_loan_after: Option<Decimal>,
-    pub commission: Option<Decimal>,
-    pub min_commission: Option<Decimal>,
-    pub max_commission: Option<Decimal>,
-    pub commission_currency: Option<String>,
-    pub warning_text: Option<String>,
-    pub completed_time: Option<String>,
-    pub completed_status: Option<String>,
+pub struct OrderState {
+    pub(crate) status: Option<String>,
+    pub(crate) init_margin_before: Option<Decimal>,
+    pub(crate) maint_margin_before: Option<Decimal>,
+    pub(crate) init_margin_change: Option<Decimal>,
+    pub(crate) equity_with_loan_value_before: Option<Decimal>,
+    pub(crate) maint_margin_change: Option<Decimal>,
+    pub(crate) equity_with_loan_change: Option<Decimal>,
+    pub(crate) init_margin_after: Option<Decimal>,
+    pub(crate) maint_margin_after: Option<Decimal>,
+    pub(crate) equity_with_loan_after: Option<Decimal>,
+    pub(crate) commission: Option<Decimal>,
+    pub(crate) min_commission: Option<Decimal>,
+    pub(crate) max_commission: Option<Decimal>,
+    pub(crate) commission_currency: Option<String>,
+    pub(crate) warning_text: Option<String>,
+    pub(crate) completed_time: Option<String>,
+    pub(crate) completed_status: Option<String>,
 }
+
+impl OrderState {
+    /// Returns the status of the order, e.g. `PreSubmitted`.
+    pub fn status(&self) -> Option<&str> {
+        self.status.as_deref()
+    }
+    /// Returns the initial margin before the order.
+    pub fn init_margin_before(&self) -> Option<Decimal> {
+        self.init_margin_before
+    }
+    /// Returns the maintenance margin before the order.
+    pub fn maint_margin_before(&self) -> Option<Decimal> {
+        self.maint_margin_before
+    }
+    /// Returns the equity with loan value before the order.
+    pub fn equity_with_loan_value_before(&self) -> Option<Decimal> {
+        self.equity_with_loan_value_before
+    }
+    /// Returns the change of the initial margin by the order.
+    pub fn init_margin_change(&self) -> Option<Decimal> {
+        self.init_margin_change
+    }
+    /// Returns the change of the maintenance margin by the order.
+    pub fn maint_margin_change(&self) -> Option<Decimal> {
+        self.maint_margin_change
+    }
+    /// Returns the change of the equity with loan value by the order.
+    pub fn equity_with_loan_change(&self) -> Option<Decimal> {
+        self.equity_with_loan_change
+    }
+    /// Returns the initial margin after the order.
+    pub fn init_margin_after(&self) -> Option<Decimal> {
+        self.init_margin_after
+    }
+    /// Returns the maintenance margin after the order.
+    pub fn maint_margin_after(&self) -> Option<Decimal> {
+        self.maint_margin_after
+    }
+    /// Returns the equity with loan value after the order.
+    pub fn equity_with_loan_after(&self) -> Option<Decimal> {
+        self.equity_with_loan_after
+    }
+    /// Returns the commission of the order.
+    pub fn commission(&self) -> Option<Decimal> {
+        self.commission
+    }
+    /// Returns the minimum commission if the commission is not known exactly.
+    pub fn min_commission(&self) -> Option<Decimal> {
+        self.min_commission
+    }
+    /// Returns the maximum commission if the commission is not known exactly.
+    pub fn max_commission(&self) -> Option<Decimal> {
+        self.max_commission
+    }
+    /// Returns the currency of the commission.
+    pub fn commission_currency(&self) -> Option<&str> {
+        self.commission_currency.as_deref()
+    }
+    /// Returns the warning of the TWS, if any.
+    pub fn warning_text(&self) -> Option<&str> {
+        self.warning_text.as_deref()
+    }
+    /// Returns the time the order was completed, as sent by the TWS.
+    pub fn completed_time(&self) -> Option<&str> {
+        self.completed_time.as_deref()
+    }
+    /// Returns the status the order was completed with.
+    pub fn completed_status(&self) -> Option<&str> {
+        self.completed_status.as_deref()
+    }
+}
+
+/// The status of an order with its filled and remaining quantity.
 #[derive(Default,Debug,Clone)]
-pub(crate) struct OrderStatus {
-    pub order_id: i32,
-    pub status: Option<String>,
-    pub filled: Option<Decimal>,
-    pub remaining: Option<Decimal>,
-    pub avg_fill_price: Option<Decimal>,
-    pub perm_id: Option<i32>,
-    pub parent_id: Option<usize>,
-    pub last_fill_price: Option<Decimal>,
-    pub client_id:  Option<usize>,
-    pub why_held: Option<String>,
-    pub mkt_cap_price: Option<f64>
+pub struct OrderStatus {
+    pub(crate) order_id: i32,
+    pub(crate) status: Option<String>,
+    pub(crate) filled: Option<Decimal>,
+    pub(crate) remaining: Option<Decimal>,
+    pub(crate) avg_fill_price: Option<Decimal>,
+    pub(crate) perm_id: Option<i32>,
+    pub(crate) parent_id: Option<usize>,
+    pub(crate) last_fill_price: Option<Decimal>,
+    pub(crate) client_id:  Option<usize>,
+    pub(crate) why_held: Option<String>,
+    pub(crate) mkt_cap_price: Option<f64>
+}
+
+impl OrderStatus {
+    /// Returns the order ID.
+    pub fn order_id(&self) -> i32 {
+        self.order_id
+    }
+    /// Returns the status of the order, e.g. `Filled`.
+    pub fn status(&self) -> Option<&str> {
+        self.status.as_deref()
+    }
+    /// Returns the filled quantity.
+    pub fn filled(&self) -> Option<Decimal> {
+        self.filled
+    }
+    /// Returns the remaining quantity.
+    pub fn remaining(&self) -> Option<Decimal> {
+        self.remaining
+    }
+    /// Returns the average price of the fills.
+    pub fn avg_fill_price(&self) -> Option<Decimal> {
+        self.avg_fill_price
+    }
+    /// Returns the permanent ID assigned by the TWS.
+    pub fn perm_id(&self) -> Option<i32> {
+        self.perm_id
+    }
+    /// Returns the ID of the parent order.
+    pub fn parent_id(&self) -> Option<usize> {
+        self.parent_id
+    }
+    /// Returns the price of the last fill.
+    pub fn last_fill_price(&self) -> Option<Decimal> {
+        self.last_fill_price
+    }
+    /// Returns the ID of the client that placed the order.
+    pub fn client_id(&self) -> Option<usize> {
+        self.client_id
+    }
+    /// Returns the reason the order is held, e.g. `locate`.
+    pub fn why_held(&self) -> Option<&str> {
+        self.why_held.as_deref()
+    }
+    /// Returns the price the order is capped at, if any.
+    pub fn mkt_cap_price(&self) -> Option<f64> {
+        self.mkt_cap_price
+    }
 }
+
+/// An execution (fill) of an order.
 #[derive(Debug,Clone)]
-pub(crate) struct Execution {
-    pub exec_id: Option<String>,
-    pub time: Option<String>,
-    pub acct_number: Option<String>,
-    pub exchange: Option<String>,
-    pub side: Option<Side>,
-    pub shares: Option<Decimal>,
-    pub price: Option<Decimal>,
-    pub perm_id: Option<i32>,
-    pub client_id: Option<usize>,
-    pub order_id: i32,
-    pub contract: Contract,
-    pub liquidation: Option<i32>,
-    pub cum_qty: Option<Decimal>,
-    pub avg_price: Option<Decimal>,
-    pub order_ref: Option<String>,
-    pub ev_rule: Option<String>,
-    pub ev_multiplier: Option<Decimal>,
-    pub model_code: Option<String>,
-    pub last_liquidity: Option<i32>
+pub struct Execution {
+    pub(crate) exec_id: Option<String>,
+    pub(crate) time: Option<String>,
+    pub(crate) acct_number: Option<String>,
+    pub(crate) exchange: Option<String>,
+    pub(crate) side: Option<Side>,
+    pub(crate) shares: Option<Decimal>,
+    pub(crate) price: Option<Decimal>,
+    pub(crate) perm_id: Option<i32>,
+    pub(crate) client_id: Option<usize>,
+    pub(crate) order_id: i32,
+    pub(crate) contract: Contract,
+    pub(crate) liquidation: Option<i32>,
+    pub(crate) cum_qty: Option<Decimal>,
+    pub(crate) avg_price: Option<Decimal>,
+    pub(crate) order_ref: Option<String>,
+    pub(crate) ev_rule: Option<String>,
+    pub(crate) ev_multiplier: Option<Decimal>,
+    pub(crate) model_code: Option<String>,
+    pub(crate) last_liquidity: Option<i32>
 }
+
+impl Execution {
+    /// Returns the time of the execution. The TWS sends it with a time zone suffix; times without one are
+    /// interpreted in the given time zone.
+    pub fn time(&self, tz: &Tz) -> Option<DateTime<Tz>> {
+        parse_tws_time(self.time.as_ref()?, tz)
+    }
+    /// Returns the executed contract.
+    pub fn contract(&self) -> &Contract {
+        &self.contract
+    }
+    /// Returns the execution ID.
+    pub fn exec_id(&self) -> Option<&str> {
+        self.exec_id.as_deref()
+    }
+    /// Returns the account of the execution.
+    pub fn acct_number(&self) -> Option<&str> {
+        self.acct_number.as_deref()
+    }
+    /// Returns the exchange the order was executed on.
+    pub fn exchange(&self) -> Option<&str> {
+        self.exchange.as_deref()
+    }
+    /// Returns the side of the execution.
+    pub fn side(&self) -> Option<&Side> {
+        self.side.as_ref()
+    }
+    /// Returns the executed quantity.
+    pub fn shares(&self) -> Option<Decimal> {
+        self.shares
+    }
+    /// Returns the execution price.
+    pub fn price(&self) -> Option<Decimal> {
+        self.price
+    }
+    /// Returns the permanent ID of the order.
+    pub fn perm_id(&self) -> Option<i32> {
+        self.perm_id
+    }
+    /// Returns the ID of the client that placed the order.
+    pub fn client_id(&self) -> Option<usize> {
+        self.client_id
+    }
+    /// Returns the order ID.
+    pub fn order_id(&self) -> i32 {
+        self.order_id
+    }
+    /// Returns whether the execution was a liquidation by the TWS.
+    pub fn liquidation(&self) -> Option<i32> {
+        self.liquidation
+    }
+    /// Returns the cumulative quantity of the order executed so far.
+    pub fn cum_qty(&self) -> Option<Decimal> {
+        self.cum_qty
+    }
+    /// Returns the average price of the order executed so far.
+    pub fn avg_price(&self) -> Option<Decimal> {
+        self.avg_price
+    }
+    /// Returns the order reference.
+    pub fn order_ref(&self) -> Option<&str> {
+        self.order_ref.as_deref()
+    }
+    /// Returns the economic value rule.
+    pub fn ev_rule(&self) -> Option<&str> {
+        self.ev_rule.as_deref()
+    }
+    /// Returns the economic value multiplier.
+    pub fn ev_multiplier(&self) -> Option<Decimal> {
+        self.ev_multiplier
+    }
+    /// Returns the model code.
+    pub fn model_code(&self) -> Option<&str> {
+        self.model_code.as_deref()
+    }
+    /// Returns whether the execution added (1) or removed (2) liquidity.
+    pub fn last_liquidity(&self) -> Option<i32> {
+        self.last_liquidity
+    }
+}
+
+/// The commission and realized P&L of an execution, identified by `exec_id`.
 #[derive(Default,Debug,Clone)]
-pub(crate) struct CommissionReport {
-    pub exec_id: Option<String>,
-    pub commission: Option<Decimal>,
-    pub currency: Option<String>,
-    pub realized_pnl: Option<Decimal>,
-    pub yield_amount: Option<Decimal>,
-    pub yield_redemption_date: Option<i32>
+pub struct CommissionReport {
+    pub(crate) exec_id: Option<String>,
+    pub(crate) commission: Option<Decimal>,
+    pub(crate) currency: Option<String>,
+    pub(crate) realized_pnl: Option<Decimal>,
+    pub(crate) yield_amount: Option<Decimal>,
+    pub(crate) yield_redemption_date: Option<i32>
+}
+
+impl CommissionReport {
+    /// Returns the ID of the execution the report belongs to.
+    pub fn exec_id(&self) -> Option<&str> {
+        self.exec_id.as_deref()
+    }
+    /// Returns the commission of the execution.
+    pub fn commission(&self) -> Option<Decimal> {
+        self.commission
+    }
+    /// Returns the currency of the commission.
+    pub fn currency(&self) -> Option<&str> {
+        self.currency.as_deref()
+    }
+    /// Returns the realized P&L of a closing execution.
+    pub fn realized_pnl(&self) -> Option<Decimal> {
+        self.realized_pnl
+    }
+    /// Returns the yield of a bond execution.
+    pub fn yield_amount(&self) -> Option<Decimal> {
+        self.yield_amount
+    }
+    /// Returns the yield redemption date of a bond execution as `yyyymmdd`.
+    pub fn yield_redemption_date(&self) -> Option<i32> {
+        self.yield_redemption_date
+    }
 }
@@ -0,0 +1,136 @@
+use chrono::{DateTime, Datelike, Duration, TimeZone, Timelike};
+use chrono_tz::Tz;
+use rust_decimal::prelude::*;
+use crate::bars::{Bar, BarSeries, Gap};
+use crate::enums::HistoricalDataBarSize;
+
+/// Returns the start of the calendar aligned bar containing `t`, e.g. the full 5 minutes, the day or the week (starting on Monday).
+fn bucket_start(t: &DateTime<Tz>, bar_size: &HistoricalDataBarSize) -> DateTime<Tz> {
+    let tz = t.timezone();
+    let date = t.date_naive();
+    let local_start = match bar_size {
+        HistoricalDataBarSize::OneDay => date,
+        HistoricalDataBarSize::OneWeek => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+        HistoricalDataBarSize::OneMonth => date.with_day(1).unwrap_or(date),
+        _ => {
+            let span = bar_size.span().num_seconds();
+            let secs = t.num_seconds_from_midnight() as i64;
+            let start = date.and_hms_opt(0, 0, 0).unwrap_or_default() + Duration::seconds(secs - secs % span);
+            return tz.from_local_datetime(&start).earliest().unwrap_or(*t);
+        }
+    };
+    tz.from_local_datetime(&local_start.and_hms_opt(0, 0, 0).unwrap_or_default()).earliest().unwrap_or(*t)
+}
+
+/// Returns the time stamp of the trading date of a session, i.e. midnight of the day the session closes.
+fn session_date(close: &DateTime<Tz>) -> DateTime<Tz> {
+    bucket_start(close, &HistoricalDataBarSize::OneDay)
+}
+
+fn aggregate(bars: &[Bar], key: impl Fn(&Bar) -> Option<DateTime<Tz>>) -> Vec<Bar> {
+    let mut data: Vec<Bar> = Vec::new();
+    let mut notional = Decimal::ZERO;
+    for bar in bars {
+        let t_stamp = match key(bar) {
+            Some(t_stamp) => t_stamp,
+            None => continue
+        };
+        match data.last_mut() {
+            Some(last) if last.t_stamp == t_stamp => {
+                last.high = last.high.max(bar.high);
+                last.low = last.low.min(bar.low);
+                last.close = bar.close;
+                last.volume += bar.volume;
+                last.count += bar.count;
+                notional += bar.wap * bar.volume;
+                if last.volume > Decimal::ZERO {last.wap = notional / last.volume}
+            },
+            _ => {
+                notional = bar.wap * bar.volume;
+                data.push(Bar {t_stamp, ..bar.clone()});
+            }
+        }
+    }
+    data
+}
+
+impl BarSeries {
+    /// Aggregates the bars into bars of the given size, e.g. one minute bars into 5 minute, hourly or daily bars.
+    /// Bars are aligned to the calendar in the time zone of the exchange: intraday bars to full multiples of the bar size
+    /// since midnight, daily bars to midnight, weekly bars to Monday and monthly bars to the first day of the month.
+    /// The WAP of an aggregated bar is the volume weighted average of the WAPs of its bars.
+    pub fn resample(&self, bar_size: HistoricalDataBarSize) -> BarSeries {
+        BarSeries {
+            start_dt: self.start_dt,
+            end_dt: self.end_dt,
+            data: aggregate(&self.data, |bar| Some(bucket_start(&bar.t_stamp, &bar_size)))
+        }
+    }
+    /// Aggregates the bars into bars of the given size within trading sessions, e.g. the liquid hours of
+    /// `ContractDetails::liquid_hours`. Intraday bars are aligned to the session open and never straddle two sessions,
+    /// daily bars aggregate a whole session and are stamped with its trading date. Bars outside the sessions are dropped.
+    pub fn resample_in_sessions(&self, bar_size: HistoricalDataBarSize, sessions: &[(DateTime<Tz>, DateTime<Tz>)]) -> BarSeries {
+        let span = bar_size.span();
+        let intraday = span < Duration::days(1);
+        let data = aggregate(&self.data, |bar| {
+            let (open, close) = sessions.iter().find(|(open, close)| *open <= bar.t_stamp && bar.t_stamp < *close)?;
+            if intraday {
+                let n_bars = (bar.t_stamp - *open).num_seconds() / span.num_seconds();
+                Some(*open + span * n_bars as i32)
+            }
+            else {
+                let date = session_date(&close.with_timezone(&bar.t_stamp.timezone()));
+                Some(bucket_start(&date, &bar_size))
+            }
+        });
+        BarSeries {start_dt: self.start_dt, end_dt: self.end_dt, data}
+    }
+    /// Detects missing bars. Without sessions, any distance between consecutive bars of more than the bar size is
+    /// reported, which includes overnight and weekend gaps for intraday bars. With sessions, intraday bars are expected
+    /// at every multiple of the bar size since the session open and daily bars for every session.
+    pub fn gaps(&self, bar_size: HistoricalDataBarSize, sessions: Option<&[(DateTime<Tz>, DateTime<Tz>)]>) -> Vec<Gap> {
+        let span = bar_size.span();
+        let sessions = match sessions {
+            Some(sessions) => sessions,
+            None => {
+                return self.data.windows(2).filter_map(|pair| {
+                    let distance = pair[1].t_stamp - pair[0].t_stamp;
+                    if distance <= span {return None}
+                    Some(Gap {
+                        start: pair[0].t_stamp + span,
+                        end: pair[1].t_stamp,
+                        missing_bars: (distance.num_seconds() / span.num_seconds() - 1).max(1) as usize
+                    })
+                }).collect();
+            }
+        };
+        let mut expected = Vec::new();
+        for (open, close) in sessions {
+            if span < Duration::days(1) {
+                let mut t = *open;
+                while t < *close {
+                    expected.push((t, std::cmp::min(t + span, *close)));
+                    t += span;
+                }
+            }
+            else {
+                let date = session_date(close);
+                expected.push((date, date + Duration::days(1)));
+            }
+        }
+        let mut gaps: Vec<Gap> = Vec::new();
+        let mut bars = self.data.iter().map(|bar| bar.t_stamp).peekable();
+        for (start, end) in expected {
+            while bars.peek().is_some_and(|t| *t < start) {bars.next();}
+            if bars.peek().is_some_and(|t| *t < end) {continue}
+            match gaps.last_mut() {
+                Some(gap) if gap.end == start => {
+                    gap.end = end;
+                    gap.missing_bars += 1;
+                },
+                _ => gaps.push(Gap {start, end, missing_bars: 1})
+            }
+        }
+        gaps
+    }
+}
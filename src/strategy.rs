@@ -0,0 +1,171 @@
+use std::{error::Error, fmt};
+use rust_decimal::Decimal;
+use crate::contract::{ComboLeg, Contract};
+use crate::enums::{ComboAction, OptionRight, SecType};
+
+#[derive(Debug)]
+struct InvalidStrategyError(String);
+
+impl Error for InvalidStrategyError {}
+
+impl fmt::Display for InvalidStrategyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid option strategy: {}", self.0) // user-facing output
+    }
+}
+
+fn invalid(reason: &str) -> Result<(), Box<dyn Error>> {
+    Err(Box::new(InvalidStrategyError(reason.to_string())))
+}
+
+/// A leg of an option strategy. `ratio` is the number of options per unit of the strategy.
+#[derive(Debug,Clone)]
+pub struct StrategyLeg {
+    pub expiry: String,
+    pub strike: Decimal,
+    pub right: OptionRight,
+    pub action: ComboAction,
+    pub ratio: i32
+}
+
+/// An option strategy on an underlying, which is turned into a BAG contract by `IBClient::qualify_strategy`.
+/// Buying the strategy buys the legs with action `Buy` and sells the legs with action `Sell`, e.g. with
+/// `Order::limit` at the net price. For non-guaranteed combos set the routing parameter with `Order::combo`.
+/// Expiries are `yyyymmdd`, strikes of spreads must be distinct and are given in ascending order.
+#[derive(Debug,Clone)]
+pub struct OptionStrategy {
+    pub underlying: Contract,
+    pub trading_class: Option<String>,
+    pub legs: Vec<StrategyLeg>
+}
+
+impl OptionStrategy {
+    fn new(underlying: &Contract, legs: Vec<StrategyLeg>) -> Self {
+        OptionStrategy {underlying: underlying.clone(), trading_class: None, legs}
+    }
+    fn leg(expiry: &str, strike: Decimal, right: &OptionRight, action: ComboAction, ratio: i32) -> StrategyLeg {
+        StrategyLeg {expiry: expiry.to_string(), strike, right: right.clone(), action, ratio}
+    }
+    /// A vertical spread buying the option at `long_strike` and selling the option at `short_strike`, e.g. a bull
+    /// call spread with the long strike below the short strike.
+    pub fn vertical(underlying: &Contract, expiry: &str, long_strike: Decimal, short_strike: Decimal, right: OptionRight) -> Result<Self, Box<dyn Error>> {
+        if long_strike == short_strike {
+            invalid("the strikes of a vertical spread must differ")?;
+        }
+        Ok(OptionStrategy::new(underlying, vec![
+            OptionStrategy::leg(expiry, long_strike, &right, ComboAction::Buy, 1),
+            OptionStrategy::leg(expiry, short_strike, &right, ComboAction::Sell, 1)
+        ]))
+    }
+    /// A calendar spread selling the option with the near expiry and buying the option with the far expiry.
+    pub fn calendar(underlying: &Contract, near_expiry: &str, far_expiry: &str, strike: Decimal, right: OptionRight) -> Result<Self, Box<dyn Error>> {
+        OptionStrategy::diagonal(underlying, near_expiry, strike, far_expiry, strike, right)
+    }
+    /// A diagonal spread selling the option with the near expiry and buying the option with the far expiry at a
+    /// different strike.
+    pub fn diagonal(underlying: &Contract, near_expiry: &str, near_strike: Decimal, far_expiry: &str, far_strike: Decimal,
+        right: OptionRight) -> Result<Self, Box<dyn Error>> {
+        if near_expiry >= far_expiry {
+            invalid("the near expiry must be before the far expiry")?;
+        }
+        Ok(OptionStrategy::new(underlying, vec![
+            OptionStrategy::leg(near_expiry, near_strike, &right, ComboAction::Sell, 1),
+            OptionStrategy::leg(far_expiry, far_strike, &right, ComboAction::Buy, 1)
+        ]))
+    }
+    /// A straddle buying a call and a put at the same strike.
+    pub fn straddle(underlying: &Contract, expiry: &str, strike: Decimal) -> Result<Self, Box<dyn Error>> {
+        Ok(OptionStrategy::new(underlying, vec![
+            OptionStrategy::leg(expiry, strike, &OptionRight::Call, ComboAction::Buy, 1),
+            OptionStrategy::leg(expiry, strike, &OptionRight::Put, ComboAction::Buy, 1)
+        ]))
+    }
+    /// A strangle buying a put at the lower strike and a call at the higher strike.
+    pub fn strangle(underlying: &Contract, expiry: &str, put_strike: Decimal, call_strike: Decimal) -> Result<Self, Box<dyn Error>> {
+        if put_strike >= call_strike {
+            invalid("the put strike of a strangle must be below the call strike")?;
+        }
+        Ok(OptionStrategy::new(underlying, vec![
+            OptionStrategy::leg(expiry, put_strike, &OptionRight::Put, ComboAction::Buy, 1),
+            OptionStrategy::leg(expiry, call_strike, &OptionRight::Call, ComboAction::Buy, 1)
+        ]))
+    }
+    /// A butterfly buying one option at the lower and upper strikes and selling two options at the middle strike.
+    pub fn butterfly(underlying: &Contract, expiry: &str, lower: Decimal, middle: Decimal, upper: Decimal, right: OptionRight) -> Result<Self, Box<dyn Error>> {
+        if !(lower < middle && middle < upper) {
+            invalid("the strikes of a butterfly must be ascending")?;
+        }
+        Ok(OptionStrategy::new(underlying, vec![
+            OptionStrategy::leg(expiry, lower, &right, ComboAction::Buy, 1),
+            OptionStrategy::leg(expiry, middle, &right, ComboAction::Sell, 2),
+            OptionStrategy::leg(expiry, upper, &right, ComboAction::Buy, 1)
+        ]))
+    }
+    /// An iron condor selling a put spread (`long_put` below `short_put`) and a call spread (`short_call` below
+    /// `long_call`). Buying the strategy receives the net credit.
+    pub fn iron_condor(underlying: &Contract, expiry: &str, long_put: Decimal, short_put: Decimal, short_call: Decimal,
+        long_call: Decimal) -> Result<Self, Box<dyn Error>> {
+        if !(long_put < short_put && short_put <= short_call && short_call < long_call) {
+            invalid("the strikes of an iron condor must be ascending")?;
+        }
+        Ok(OptionStrategy::new(underlying, vec![
+            OptionStrategy::leg(expiry, long_put, &OptionRight::Put, ComboAction::Buy, 1),
+            OptionStrategy::leg(expiry, short_put, &OptionRight::Put, ComboAction::Sell, 1),
+            OptionStrategy::leg(expiry, short_call, &OptionRight::Call, ComboAction::Sell, 1),
+            OptionStrategy::leg(expiry, long_call, &OptionRight::Call, ComboAction::Buy, 1)
+        ]))
+    }
+    /// A ratio spread buying `long_ratio` options at `long_strike` and selling `short_ratio` options at `short_strike`.
+    pub fn ratio_spread(underlying: &Contract, expiry: &str, long_strike: Decimal, long_ratio: i32, short_strike: Decimal,
+        short_ratio: i32, right: OptionRight) -> Result<Self, Box<dyn Error>> {
+        if long_strike == short_strike {
+            invalid("the strikes of a ratio spread must differ")?;
+        }
+        if long_ratio < 1 || short_ratio < 1 {
+            invalid("the ratios of a ratio spread must be positive")?;
+        }
+        Ok(OptionStrategy::new(underlying, vec![
+            OptionStrategy::leg(expiry, long_strike, &right, ComboAction::Buy, long_ratio),
+            OptionStrategy::leg(expiry, short_strike, &right, ComboAction::Sell, short_ratio)
+        ]))
+    }
+    /// Sets the trading class of the legs, e.g. `SPXW` to trade the weekly SPX options.
+    pub fn with_trading_class(mut self, trading_class: &str) -> Self {
+        self.trading_class = Some(trading_class.to_string());
+        self
+    }
+    /// Returns the exchange of the legs and the combo: SMART, or the exchange of the underlying for futures options.
+    fn exchange(&self) -> Option<String> {
+        match self.underlying.sec_type {
+            Some(SecType::Future) => self.underlying.exchange.clone(),
+            _ => Some("SMART".to_string())
+        }
+    }
+    /// Returns the option contracts of the legs, which are not qualified yet.
+    pub fn leg_contracts(&self) -> Vec<Contract> {
+        let sec_type = match self.underlying.sec_type {
+            Some(SecType::Future) => SecType::OptionOnFuture,
+            _ => SecType::Option
+        };
+        self.legs.iter().map(|leg| Contract {
+            symbol: self.underlying.symbol.clone(),
+            sec_type: Some(sec_type.clone()),
+            last_trade_date_or_contract_month: Some(leg.expiry.clone()),
+            strike: Some(leg.strike),
+            right: Some(leg.right.clone()),
+            exchange: self.exchange(),
+            currency: self.underlying.currency.clone(),
+            trading_class: self.trading_class.clone(),
+            ..Default::default()
+        }).collect()
+    }
+    /// Creates the BAG contract from the qualified legs, which are given in the order of `leg_contracts`.
+    pub(crate) fn combo(&self, qualified_legs: &[Contract]) -> Option<Contract> {
+        let exchange = self.exchange()?;
+        let mut combo = Contract::combo(self.underlying.symbol.as_ref()?, &exchange, self.underlying.currency.as_ref()?);
+        for (leg, contract) in self.legs.iter().zip(qualified_legs) {
+            combo.add_leg(ComboLeg::new(contract.con_id()?, leg.ratio, leg.action.clone(), &exchange));
+        }
+        Some(combo)
+    }
+}
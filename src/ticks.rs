@@ -0,0 +1,175 @@
+use chrono::{DateTime, Utc};
+use log::warn;
+use rust_decimal::prelude::*;
+use crate::enums::HistoricalTicksType;
+
+/// A historical midpoint tick.
+#[derive(Debug,Clone)]
+pub struct HistoricalTick {
+    pub time: DateTime<Utc>,
+    pub price: Decimal,
+    pub size: Decimal
+}
+
+/// A historical bid/ask tick.
+#[derive(Debug,Clone)]
+pub struct HistoricalTickBidAsk {
+    pub time: DateTime<Utc>,
+    pub bid_price: Decimal,
+    pub ask_price: Decimal,
+    pub bid_size: Decimal,
+    pub ask_size: Decimal,
+    pub ask_past_high: bool,
+    pub bid_past_low: bool
+}
+
+/// A historical trade tick.
+#[derive(Debug,Clone)]
+pub struct HistoricalTickLast {
+    pub time: DateTime<Utc>,
+    pub price: Decimal,
+    pub size: Decimal,
+    pub exchange: Option<String>,
+    pub special_conditions: Option<String>,
+    pub past_limit: bool,
+    pub unreported: bool
+}
+
+/// Time-ordered historical ticks as returned by `req_historical_ticks`. The variant depends on the requested
+/// `HistoricalTicksType`.
+#[derive(Debug,Clone)]
+pub enum HistoricalTicks {
+    Midpoint(Vec<HistoricalTick>),
+    BidAsk(Vec<HistoricalTickBidAsk>),
+    Trades(Vec<HistoricalTickLast>)
+}
+
+impl HistoricalTicks {
+    pub(crate) fn new(kind: &HistoricalTicksType) -> Self {
+        match kind {
+            HistoricalTicksType::Midpoint => HistoricalTicks::Midpoint(Vec::new()),
+            HistoricalTicksType::BidAsk => HistoricalTicks::BidAsk(Vec::new()),
+            HistoricalTicksType::Trades => HistoricalTicks::Trades(Vec::new())
+        }
+    }
+    /// Returns the number of ticks.
+    pub fn len(&self) -> usize {
+        match self {
+            HistoricalTicks::Midpoint(ticks) => ticks.len(),
+            HistoricalTicks::BidAsk(ticks) => ticks.len(),
+            HistoricalTicks::Trades(ticks) => ticks.len()
+        }
+    }
+    /// Returns `true` if no ticks were received.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Returns the time of the last tick, if any.
+    pub fn last_time(&self) -> Option<DateTime<Utc>> {
+        match self {
+            HistoricalTicks::Midpoint(ticks) => ticks.last().map(|t| t.time),
+            HistoricalTicks::BidAsk(ticks) => ticks.last().map(|t| t.time),
+            HistoricalTicks::Trades(ticks) => ticks.last().map(|t| t.time)
+        }
+    }
+    /// Returns the number of ticks with the given time stamp.
+    pub(crate) fn count_at(&self, time: &DateTime<Utc>) -> usize {
+        match self {
+            HistoricalTicks::Midpoint(ticks) => ticks.iter().rev().take_while(|t| t.time == *time).count(),
+            HistoricalTicks::BidAsk(ticks) => ticks.iter().rev().take_while(|t| t.time == *time).count(),
+            HistoricalTicks::Trades(ticks) => ticks.iter().rev().take_while(|t| t.time == *time).count()
+        }
+    }
+    /// Appends the ticks of another page of the same type, skipping the first `skip` ticks (which were already
+    /// received with the previous page) and all ticks after `end`.
+    pub(crate) fn append_page(&mut self, page: HistoricalTicks, skip: usize, end: &DateTime<Utc>) {
+        match (self, page) {
+            (HistoricalTicks::Midpoint(ticks), HistoricalTicks::Midpoint(new)) =>
+                ticks.extend(new.into_iter().skip(skip).take_while(|t| t.time <= *end)),
+            (HistoricalTicks::BidAsk(ticks), HistoricalTicks::BidAsk(new)) =>
+                ticks.extend(new.into_iter().skip(skip).take_while(|t| t.time <= *end)),
+            (HistoricalTicks::Trades(ticks), HistoricalTicks::Trades(new)) =>
+                ticks.extend(new.into_iter().skip(skip).take_while(|t| t.time <= *end)),
+            _ => ()
+        }
+    }
+}
+
+/// Returns the start of the next page of a paged tick request, or `None` if the page was the last one: it was not
+/// full or reached past `end`. The next page starts at the second of the last tick, so ticks at that second that
+/// did not fit into the page are received again and skipped with `count_at`. If a full page shares the second of
+/// the cursor, the remaining ticks of that second cannot be requested and the next page starts one second later.
+pub(crate) fn next_page_start(cursor: &DateTime<Utc>, page_end: &DateTime<Utc>, n_page: usize, page_size: usize,
+    end: &DateTime<Utc>) -> Option<DateTime<Utc>> {
+    if n_page < page_size || page_end > end {
+        return None;
+    }
+    if page_end > cursor {
+        Some(*page_end)
+    } else {
+        warn!("More than {} ticks at {}, skipping to the next second.", page_size, cursor);
+        Some(*cursor + chrono::Duration::seconds(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use crate::enums::constants::MAX_HISTORICAL_TICKS;
+
+    fn page(times: &[(i64, usize)]) -> HistoricalTicks {
+        HistoricalTicks::Midpoint(times.iter().flat_map(|(second, n)| (0..*n).map(move |i| HistoricalTick {
+            time: Utc.timestamp_opt(1677681000 + second, 0).unwrap(),
+            price: Decimal::new(i as i64, 0),
+            size: Decimal::ONE
+        })).collect())
+    }
+
+    /// Pages through the given pages as `req_historical_ticks` does and returns the ticks and the page starts.
+    fn download(pages: Vec<HistoricalTicks>, end: i64) -> (HistoricalTicks, Vec<i64>) {
+        let end = Utc.timestamp_opt(1677681000 + end, 0).unwrap();
+        let mut cursor = Utc.timestamp_opt(1677681000, 0).unwrap();
+        let mut starts = vec![];
+        let mut ticks = HistoricalTicks::new(&HistoricalTicksType::Midpoint);
+        for page in pages {
+            starts.push(cursor.timestamp() - 1677681000);
+            let n_page = page.len();
+            let page_end = page.last_time().unwrap();
+            let skip = ticks.count_at(&cursor);
+            ticks.append_page(page, skip, &end);
+            match next_page_start(&cursor, &page_end, n_page, MAX_HISTORICAL_TICKS, &end) {
+                Some(next) => cursor = next,
+                None => break
+            }
+        }
+        (ticks, starts)
+    }
+
+    #[test]
+    fn last_page() {
+        let (ticks, starts) = download(vec![page(&[(0, 10), (5, 20)]), page(&[(6, 1)])], 60);
+        assert_eq!((ticks.len(), starts), (30, vec![0]));
+        let (ticks, _) = download(vec![page(&[(30, MAX_HISTORICAL_TICKS - 1), (90, 1)])], 60);
+        assert_eq!(ticks.len(), MAX_HISTORICAL_TICKS - 1);
+    }
+
+    #[test]
+    fn pages_overlap_at_last_second() {
+        let first = page(&[(0, MAX_HISTORICAL_TICKS - 10), (5, 10)]);
+        let second = page(&[(5, 15), (7, 2)]);
+        let (ticks, starts) = download(vec![first, second], 60);
+        assert_eq!(starts, vec![0, 5]);
+        assert_eq!(ticks.len(), MAX_HISTORICAL_TICKS + 7);
+        assert_eq!(ticks.count_at(&Utc.timestamp_opt(1677681007, 0).unwrap()), 2);
+    }
+
+    #[test]
+    fn full_page_at_one_second() {
+        let full = || page(&[(5, MAX_HISTORICAL_TICKS)]);
+        let (ticks, starts) = download(vec![full(), full(), page(&[(6, 3)])], 60);
+        //the second page repeats the first and is skipped, then the cursor moves past the crowded second
+        assert_eq!(starts, vec![0, 5, 6]);
+        assert_eq!(ticks.len(), MAX_HISTORICAL_TICKS + 3);
+    }
+}
@@ -5,6 +5,13 @@ use tokio::time;
 use chrono::Duration;
 use chrono::{TimeZone, Utc, DateTime};
 use rs_ib_api::enums::*;
+use rs_ib_api::market_rule::*;
+use rs_ib_api::bars::*;
+use rs_ib_api::indicators::*;
+use rs_ib_api::calendar::*;
+use rs_ib_api::opt_params::*;
+use rs_ib_api::futures_chain::*;
+use rs_ib_api::strategy::*;
 use rust_decimal::prelude::*;
 
 #[tokio::test]
@@ -25,8 +32,8 @@ async fn contract_details() {
     let contract = Contract::stock("SPY", "ARCA", "USD");
     match client.req_contract_details(&contract).await {
         Ok(details) => for detail in &details {
-            match &detail.contract() {
-                Some(contract) => assert_eq!(contract.symbol(), &Some("SPY".to_string())),
+            match detail.contract() {
+                Some(contract) => assert_eq!(contract.symbol(), Some("SPY")),
                 None => panic!("No valid contract details returned for SPY")
             }
         }
@@ -43,7 +50,7 @@ async fn opt_params() {
     let contract = Contract::stock("SPY", "ARCA", "USD");
     match client.req_contract_details(&contract).await {
         Ok(details) => for detail in &details {
-            match &detail.contract() {
+            match detail.contract() {
                 Some(contract) => {
                     match client.req_options_metadata(&contract, None).await {
                         Ok(params) => (),
@@ -175,8 +182,782 @@ async fn historical_data() {
     match &client.req_historical_data(&contract, &end_dt.unwrap(), HistoricalDataDuration::Months(1), HistoricalDataBarSize::OneDay,
     HistoricalDataType::Midpoint, true).await {
         Ok(bars) => {
-            assert!(bars.n_bars.is_some());
+            assert!(!bars.data.is_empty());
         },
         Err(_error) => panic!("Bar series loading not successful!")
     }
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn historical_bars_subscription() {
+    let mut client = match IBClient::connect(4002, 5, "", None).await {
+        Ok(client) => client,
+        Err(_error) => panic!("Connection not successful!")
+    };
+    let contract = Contract::stock("AAPL", "SMART", "USD");
+    match client.subscribe_historical_bars(&contract, HistoricalDataDuration::Days(1), HistoricalDataBarSize::FiveMins,
+    HistoricalDataType::Trades, false).await {
+        Ok(mut subscription) => {
+            assert!(subscription.last_bar().is_some());
+            let n_bars = subscription.series().data.len();
+            tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+            subscription.update();
+            assert!(subscription.series().data.len() >= n_bars);
+        },
+        Err(_error) => panic!("Bar subscription not successful!")
+    }
+}
+
+#[tokio::test]
+async fn historical_ticks() {
+    let mut client = match IBClient::connect(4002, 6, "", None).await {
+        Ok(client) => client,
+        Err(_error) => panic!("Connection not successful!")
+    };
+    let contract = Contract::stock("AAPL", "SMART", "USD");
+    let start = Utc.datetime_from_str("2023-03-01 15:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+    let end = start + Duration::minutes(5);
+    match client.req_historical_ticks(&contract, &start, &end, 1000, HistoricalTicksType::Trades, true).await {
+        Ok(ticks) => {
+            assert!(ticks.len() > 1000);
+            assert!(ticks.last_time().unwrap() <= end);
+        },
+        Err(_error) => panic!("Historical tick request not successful!")
+    }
+}
+
+#[tokio::test]
+async fn head_timestamp_and_histogram() {
+    let mut client = match IBClient::connect(4002, 7, "", None).await {
+        Ok(client) => client,
+        Err(_error) => panic!("Connection not successful!")
+    };
+    let contract = Contract::stock("AAPL", "SMART", "USD");
+    match client.req_head_timestamp(&contract, HistoricalDataType::Trades, true).await {
+        Ok(head) => assert!(head < Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap()),
+        Err(_error) => panic!("Head time stamp request not successful!")
+    }
+    match client.req_histogram_data(&contract, true, HistogramPeriod::Days(3)).await {
+        Ok(histogram) => assert!(!histogram.is_empty()),
+        Err(_error) => panic!("Histogram request not successful!")
+    }
+}
+
+#[tokio::test]
+async fn historical_backfill() {
+    let mut client = match IBClient::connect(4002, 8, "", None).await {
+        Ok(client) => client,
+        Err(_error) => panic!("Connection not successful!")
+    };
+    let contract = Contract::stock("AAPL", "SMART", "USD");
+    let start = Utc.with_ymd_and_hms(2023, 2, 1, 0, 0, 0).unwrap();
+    let end = Utc.with_ymd_and_hms(2023, 3, 1, 0, 0, 0).unwrap();
+    match client.backfill_bars(&contract, &start, &end, HistoricalDataBarSize::FiveMins, HistoricalDataType::Trades, true).await {
+        Ok(backfill) => {
+            assert!(backfill.is_complete());
+            assert!(backfill.series.data.windows(2).all(|bars| bars[0].t_stamp < bars[1].t_stamp));
+            assert!(backfill.series.data.first().unwrap().t_stamp >= start);
+        },
+        Err(_error) => panic!("Backfill not successful!")
+    }
+}
+
+#[tokio::test]
+async fn historical_store() {
+    let mut client = match IBClient::connect(4002, 9, "", None).await {
+        Ok(client) => client,
+        Err(_error) => panic!("Connection not successful!")
+    };
+    let store_dir = std::env::temp_dir().join("rs_ib_api_store");
+    client.set_historical_store(Some(rs_ib_api::historical::HistoricalStore::open(&store_dir).unwrap()));
+    let contract = Contract::stock("SPY", "ARCA", "USD");
+    let start = Utc.with_ymd_and_hms(2023, 1, 3, 0, 0, 0).unwrap();
+    let end = Utc.with_ymd_and_hms(2023, 1, 6, 0, 0, 0).unwrap();
+    let first = client.backfill_bars(&contract, &start, &end, HistoricalDataBarSize::OneMin, HistoricalDataType::Trades, true).await.unwrap();
+    let sent = client.historical_requests_in_window();
+    let second = client.backfill_bars(&contract, &start, &end, HistoricalDataBarSize::OneMin, HistoricalDataType::Trades, true).await.unwrap();
+    assert!(!first.series.data.is_empty());
+    assert_eq!(first.series.data.len(), second.series.data.len());
+    //a plain request for a stored range is served from the store
+    let stored = client.req_historical_data(&contract, &end, HistoricalDataDuration::Days(1), HistoricalDataBarSize::OneMin,
+        HistoricalDataType::Trades, true).await.unwrap();
+    assert!(!stored.data.is_empty());
+    assert_eq!(client.historical_requests_in_window(), sent);
+    std::fs::remove_dir_all(store_dir).unwrap();
+}
+
+#[tokio::test]
+async fn cancel_historical_data() {
+    let mut client = match IBClient::connect(4002, 10, "", None).await {
+        Ok(client) => client,
+        Err(_error) => panic!("Connection not successful!")
+    };
+    let contract = Contract::stock("AAPL", "SMART", "USD");
+    let end_dt = Utc.with_ymd_and_hms(2023, 3, 1, 0, 0, 0).unwrap();
+    let request = client.req_historical_data(&contract, &end_dt, HistoricalDataDuration::Seconds(1800), HistoricalDataBarSize::OneSec,
+        HistoricalDataType::Trades, false);
+    assert!(time::timeout(time::Duration::from_millis(1), request).await.is_err());
+    match client.req_historical_data(&contract, &end_dt, HistoricalDataDuration::Months(1), HistoricalDataBarSize::OneDay,
+    HistoricalDataType::Trades, true).await {
+        Ok(bars) => assert!(!bars.data.is_empty()),
+        Err(_error) => panic!("Bar series loading after cancellation not successful!")
+    }
+}
+
+#[tokio::test]
+async fn trading_schedule() {
+    let mut client = match IBClient::connect(4002, 11, "", None).await {
+        Ok(client) => client,
+        Err(_error) => panic!("Connection not successful!")
+    };
+    let contract = Contract::stock("AAPL", "SMART", "USD");
+    let end = Utc.with_ymd_and_hms(2023, 7, 10, 0, 0, 0).unwrap();
+    match client.req_trading_schedule(&contract, &end, HistoricalDataDuration::Weeks(2), true).await {
+        Ok(schedule) => {
+            assert_eq!(schedule.time_zone, chrono_tz::US::Eastern);
+            //early close on July 3rd, holiday on July 4th
+            let early_close = schedule.sessions.iter().find(|s| s.ref_date == chrono::NaiveDate::from_ymd_opt(2023, 7, 3).unwrap()).unwrap();
+            assert_eq!(early_close.end, chrono_tz::US::Eastern.with_ymd_and_hms(2023, 7, 3, 13, 0, 0).unwrap());
+            assert!(schedule.sessions.iter().all(|s| s.ref_date != chrono::NaiveDate::from_ymd_opt(2023, 7, 4).unwrap()));
+        },
+        Err(_error) => panic!("Trading schedule request not successful!")
+    }
+}
+
+#[cfg(feature = "serde")]
+#[tokio::test]
+async fn qualify_contracts() {
+    let mut client = match IBClient::connect(4002, 12, "", None).await {
+        Ok(client) => client,
+        Err(_error) => panic!("Connection not successful!")
+    };
+    let cache_path = std::env::temp_dir().join("rs_ib_api_contracts.json");
+    client.set_contract_cache(ContractCache::open(&cache_path).unwrap());
+    let mut contract = Contract::stock_us_smart("AAPL");
+    client.qualify(&mut contract).await.unwrap();
+    assert_eq!(contract.con_id(), Some(265598));
+    let qualified = client.qualify_many(&[Contract::stock_us_smart("MSFT"), Contract::stock_us_smart("AAPL")]).await.unwrap();
+    assert_eq!(qualified[1].con_id(), Some(265598));
+    assert!(client.qualify(&mut Contract::stock("AAPL", "SMART", "EUR")).await.is_err());
+    let reopened = ContractCache::open(&cache_path).unwrap();
+    assert_eq!(reopened.get(&Contract::stock_us_smart("AAPL")).and_then(|c| c.con_id()), Some(265598));
+    std::fs::remove_file(cache_path).unwrap();
+}
+
+#[tokio::test]
+async fn option_chain() {
+    let mut client = match IBClient::connect(4002, 13, "", None).await {
+        Ok(client) => client,
+        Err(_error) => panic!("Connection not successful!")
+    };
+    let chain = client.req_option_chain(&Contract::stock_us_smart("SPY")).await.unwrap();
+    assert!(chain.params.len() > 1);
+    let expiry = chain.expirations()[0].clone();
+    let strikes = chain.strikes();
+    let atm = strikes[strikes.len() / 2];
+    let expiry_date = chrono::NaiveDate::parse_from_str(&expiry, "%Y%m%d").unwrap();
+    let near = chain.dte_range(expiry_date, 0, 0).strikes_around(atm, 2);
+    let options = client.qualify_option_chain(&near).await.unwrap();
+    assert!(!options.is_empty() && options.len() <= 4);
+    assert!(options.iter().all(|option| option.con_id().is_some()));
+}
+
+#[tokio::test]
+async fn continuous_futures() {
+    let mut client = match IBClient::connect(4002, 14, "", None).await {
+        Ok(client) => client,
+        Err(_error) => panic!("Connection not successful!")
+    };
+    let contfut = Contract::continuous_future("ES", "CME", "USD").unwrap();
+    let series = client.req_historical_data(&contfut, &Utc::now(), HistoricalDataDuration::Months(1),
+        HistoricalDataBarSize::OneDay, HistoricalDataType::Trades, true).await.unwrap();
+    assert!(!series.data.is_empty());
+    let chain = client.req_futures_chain("ES", "CME", "USD").await.unwrap();
+    let today = Utc::now().date_naive();
+    let front = chain.front(today, 8).unwrap();
+    assert!(front.last_trade_date > today);
+    assert_eq!(chain.roll_dates(8).len(), chain.contracts.len() - 1);
+}
+
+#[tokio::test]
+async fn option_strategy_order() {
+    let mut client = match IBClient::connect(4002, 15, "", None).await {
+        Ok(client) => client,
+        Err(_error) => panic!("Connection not successful!")
+    };
+    let spy = Contract::stock_us_smart("SPY");
+    let chain = client.req_option_chain(&spy).await.unwrap();
+    let expiry = chain.expirations()[1].clone();
+    let strikes = chain.strikes();
+    let atm = strikes.len() / 2;
+    let spread = OptionStrategy::vertical(&spy, &expiry, strikes[atm], strikes[atm + 1], OptionRight::Call).unwrap()
+        .with_trading_class("SPY");
+    let combo = client.qualify_strategy(&spread).await.unwrap();
+    let order = Order::limit(combo, Action::Buy, Decimal::new(1, 0), Decimal::new(1, 2), TimeInForce::Day).combo();
+    let tracker = client.place_order(&order).await.unwrap();
+    assert!(tracker.status().is_some());
+}
+
+#[tokio::test]
+async fn bond_contract_details() {
+    let mut client = match IBClient::connect(4002, 16, "", None).await {
+        Ok(client) => client,
+        Err(_error) => panic!("Connection not successful!")
+    };
+    //US Treasury note 2.25% 15 Nov 2025
+    let bond = Contract::bond(SecIdType::Cusip, "912828M56", "SMART", "USD").unwrap();
+    let details = client.req_contract_details(&bond).await.unwrap();
+    assert_eq!(details.len(), 1);
+    assert_eq!(details[0].cusip(), Some("912828M56"));
+    assert_eq!(details[0].coupon(), Some(Decimal::new(225, 2)));
+    assert_eq!(details[0].maturity(), chrono::NaiveDate::from_ymd_opt(2025, 11, 15));
+    assert!(details[0].issue_date().is_some());
+    assert_eq!(details[0].is_callable(), Some(false));
+}
+
+#[tokio::test]
+async fn security_id_lookup() {
+    let mut client = match IBClient::connect(4002, 17, "", None).await {
+        Ok(client) => client,
+        Err(_error) => panic!("Connection not successful!")
+    };
+    let apple = client.lookup_by_sec_id(SecIdType::Isin, "US0378331005").await.unwrap();
+    assert!(apple.iter().any(|contract| contract.con_id() == Some(265598)));
+    let matches = client.search_symbols("AAPL").await.unwrap();
+    let aapl = matches.iter().find(|description| description.contract().and_then(|c| c.con_id()) == Some(265598)).unwrap();
+    assert!(aapl.derivative_sec_types().iter().any(|sec_type| sec_type == "OPT"));
+}
+
+#[tokio::test]
+async fn contract_details_accessors() {
+    let mut client = match IBClient::connect(4002, 18, "", None).await {
+        Ok(client) => client,
+        Err(_error) => panic!("Connection not successful!")
+    };
+    let details = client.req_contract_details(&Contract::stock_us_smart("AAPL")).await.unwrap();
+    let detail = &details[0];
+    assert_eq!(detail.long_name(), Some("APPLE INC"));
+    assert_eq!(detail.min_tick(), Some(Decimal::new(1, 2)));
+    assert!(detail.order_types().contains(&"LMT"));
+    assert!(detail.valid_exchanges().contains(&"SMART"));
+    assert_eq!(detail.valid_exchanges().len(), detail.market_rule_ids().len());
+    assert!(detail.size_increment().is_some());
+    assert!(detail.sec_id_list().iter().any(|(id_type, id)| id_type == "ISIN" && id == "US0378331005"));
+    let contract = detail.contract().unwrap();
+    assert_eq!(contract.sec_type(), Some(&SecType::Stock));
+    assert_eq!(contract.currency(), Some("USD"));
+    assert_eq!(contract.primary_exchange(), Some("NASDAQ"));
+}
+
+#[test]
+fn market_rule_rounding() {
+    let rule = MarketRule {
+        id: 26,
+        increments: vec![
+            PriceIncrement {low_edge: Decimal::new(0, 0), increment: Decimal::new(1, 4)},
+            PriceIncrement {low_edge: Decimal::new(1, 0), increment: Decimal::new(1, 2)},
+        ]
+    };
+    assert_eq!(rule.round(Decimal::new(123456, 3)), Decimal::new(12346, 2));
+    assert_eq!(rule.round_down(Decimal::new(123456, 3)), Decimal::new(12345, 2));
+    assert_eq!(rule.round_up(Decimal::new(123451, 3)), Decimal::new(12346, 2));
+    assert_eq!(rule.round(Decimal::new(512345, 6)), Decimal::new(5123, 4));
+    let min_tick_rule = MarketRule::from_min_tick(Decimal::new(5, 2));
+    assert_eq!(min_tick_rule.round(Decimal::new(150123, 3)), Decimal::new(15010, 2));
+    assert_eq!(min_tick_rule.round(Decimal::new(-150176, 3)), Decimal::new(-15020, 2));
+}
+
+#[test]
+fn historical_data_limits() {
+    assert_eq!(HistoricalDataBarSize::OneSec.max_duration(), HistoricalDataDuration::Seconds(1800));
+    assert_eq!(HistoricalDataBarSize::FiveMins.max_duration(), HistoricalDataDuration::Weeks(1));
+    assert_eq!(HistoricalDataBarSize::OneDay.max_duration(), HistoricalDataDuration::Years(1));
+    assert!(HistoricalDataDuration::Years(5).min_span() > HistoricalDataBarSize::OneSec.max_duration().max_span());
+    assert!(HistoricalDataDuration::Seconds(60).max_span() < HistoricalDataBarSize::OneDay.span());
+    assert_eq!(HistoricalDataDuration::Months(1).min_span(), Duration::days(28));
+    assert_eq!(HistoricalDataDuration::Months(1).max_span(), Duration::days(31));
+}
+
+#[test]
+fn historical_request_validation() {
+    use rs_ib_api::historical::{validate_bars, validate_subscription};
+    use HistoricalDataBarSize::*;
+    use HistoricalDataDuration::*;
+    let stock = Some(&SecType::Stock);
+    let trades = Some(&HistoricalDataType::Trades);
+    assert!(validate_bars(stock, &Days(1), &OneMin, trades).is_ok());
+    assert!(validate_bars(stock, &Years(2), &OneSec, trades).is_err());
+    assert!(validate_bars(stock, &Days(0), &OneMin, trades).is_err());
+    assert!(validate_bars(stock, &Weeks(-1), &OneMin, trades).is_err());
+    assert!(validate_bars(stock, &Seconds(90000), &OneMin, trades).is_err());
+    assert!(validate_bars(stock, &Seconds(60), &OneDay, trades).is_err());
+    assert!(validate_bars(Some(&SecType::Index), &Days(1), &OneMin, Some(&HistoricalDataType::BidAsk)).is_err());
+    assert!(validate_bars(Some(&SecType::Forex), &Days(1), &OneMin, trades).is_err());
+    assert!(validate_bars(Some(&SecType::Future), &Days(1), &OneMin, None).is_err());
+    let error = validate_bars(stock, &Days(1), &OneDay, Some(&HistoricalDataType::Schedule)).unwrap_err();
+    assert!(error.reason().contains("req_trading_schedule"));
+    assert!(validate_subscription(stock, &Seconds(3600), &FiveSecs, &HistoricalDataType::Trades).is_ok());
+    assert!(validate_subscription(stock, &Seconds(1800), &OneSec, &HistoricalDataType::Trades).is_err());
+    assert!(validate_subscription(Some(&SecType::Index), &Days(1), &OneMin, &HistoricalDataType::Midpoint).is_err());
+}
+
+#[cfg(any(feature = "csv", feature = "serde", feature = "arrow"))]
+fn sample_bars() -> BarSeries {
+    let tz = chrono_tz::US::Eastern;
+    let bar = |minute: u32, close: f64| Bar {
+        t_stamp: tz.with_ymd_and_hms(2023, 3, 1, 9, 30 + minute, 0).unwrap(),
+        open: 100.0, high: 101.5, low: 99.25, close,
+        wap: Decimal::new(10012345, 5),
+        volume: Decimal::new(15000, 0),
+        count: 42
+    };
+    BarSeries {
+        start_dt: Some(tz.with_ymd_and_hms(2023, 3, 1, 9, 30, 0).unwrap()),
+        end_dt: Some(tz.with_ymd_and_hms(2023, 3, 1, 9, 32, 0).unwrap()),
+        data: vec![bar(0, 100.5), bar(1, 101.0)]
+    }
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn bar_series_csv() {
+    let series = sample_bars();
+    let mut csv = Vec::new();
+    series.write_csv(&mut csv).unwrap();
+    let csv_text = String::from_utf8(csv.clone()).unwrap();
+    assert!(csv_text.starts_with("time,open,high,low,close,wap,volume,count\n2023-03-01T09:30:00-05:00,100,"));
+    let from_csv = BarSeries::read_csv(csv.as_slice(), &chrono_tz::US::Eastern).unwrap();
+    assert_eq!(from_csv.data.len(), 2);
+    assert_eq!(from_csv.data[1].t_stamp, series.data[1].t_stamp);
+    assert_eq!(from_csv.data[0].wap, series.data[0].wap);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn bar_series_json() {
+    let series = sample_bars();
+    let mut json = Vec::new();
+    series.write_json(&mut json).unwrap();
+    let from_json = BarSeries::read_json(json.as_slice()).unwrap();
+    assert_eq!(from_json.start_dt, series.start_dt);
+    assert_eq!(from_json.data[0].t_stamp.timezone(), chrono_tz::US::Eastern);
+    assert_eq!(from_json.data[1].close, 101.0);
+    assert_eq!(from_json.data[0].wap, series.data[0].wap);
+    assert_eq!(from_json.data[0].volume, series.data[0].volume);
+}
+
+/// Returns a series whose WAP and volume cannot be represented exactly as `f64`.
+#[cfg(any(feature = "serde", feature = "arrow"))]
+fn precise_bars() -> BarSeries {
+    let mut series = sample_bars();
+    series.data[0].wap = Decimal::new(1, 1);
+    series.data[0].volume = Decimal::from_str("1234567890.123456789").unwrap();
+    series.data[1].wap = Decimal::from_str("0.30000000000000000001").unwrap();
+    series
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn bar_series_json_decimals() {
+    let series = precise_bars();
+    let mut json = Vec::new();
+    series.write_json(&mut json).unwrap();
+    assert!(String::from_utf8(json.clone()).unwrap().contains("\"wap\":\"0.1\""));
+    let from_json = BarSeries::read_json(json.as_slice()).unwrap();
+    for (read, written) in from_json.data.iter().zip(&series.data) {
+        assert_eq!((read.wap, read.volume), (written.wap, written.volume));
+    }
+}
+
+#[cfg(feature = "arrow")]
+#[test]
+fn bar_series_arrow() {
+    use arrow_schema::{DataType, TimeUnit};
+    let series = sample_bars();
+    let batch = series.to_record_batch().unwrap();
+    assert_eq!(batch.num_rows(), 2);
+    assert_eq!(batch.schema().as_ref(), &BarSeries::arrow_schema(&chrono_tz::US::Eastern));
+    let names: Vec<&str> = batch.schema_ref().fields().iter().map(|field| field.name().as_str()).collect();
+    assert_eq!(names, vec!["time", "open", "high", "low", "close", "wap", "volume", "count"]);
+    assert_eq!(batch.schema().field(0).data_type(), &DataType::Timestamp(TimeUnit::Second, Some("US/Eastern".into())));
+    let from_batch = BarSeries::from_record_batch(&batch).unwrap();
+    assert_eq!(from_batch.data.len(), 2);
+    assert_eq!(from_batch.data[1].t_stamp, series.data[1].t_stamp);
+    assert_eq!(from_batch.data[1].close, 101.0);
+    assert_eq!(from_batch.data[0].count, 42);
+
+    let series = precise_bars();
+    let batch = series.to_record_batch().unwrap();
+    assert_eq!(batch.schema().field(5).data_type(), &DataType::Decimal128(38, 18));
+    let from_batch = BarSeries::from_record_batch(&batch).unwrap();
+    assert_eq!(from_batch.data[0].wap, Decimal::new(1, 1));
+    assert_eq!(from_batch.data[0].volume, series.data[0].volume);
+    //values with more than 18 decimal places are rounded
+    assert_eq!(from_batch.data[1].wap, Decimal::from_str("0.3").unwrap());
+}
+
+#[test]
+fn bar_series_resample_and_gaps() {
+    let tz = chrono_tz::US::Eastern;
+    let minute_bar = |day: u32, hour: u32, minute: u32, price: f64| Bar {
+        t_stamp: tz.with_ymd_and_hms(2023, 3, day, hour, minute, 0).unwrap(),
+        open: price, high: price + 1.0, low: price - 1.0, close: price + 0.5,
+        wap: Decimal::new(price as i64, 0),
+        volume: Decimal::new(100 * (minute as i64 % 30 + 1), 0),
+        count: 1
+    };
+    let mut data: Vec<Bar> = (0..10).map(|i| minute_bar(1, 9, 30 + i, 100.0 + i as f64)).collect();
+    data.remove(7); //missing bar at 09:37
+    data.push(minute_bar(1, 16, 5, 120.0)); //after hours
+    data.push(minute_bar(2, 9, 30, 130.0));
+    let series = BarSeries {start_dt: None, end_dt: None, data};
+
+    let five_mins = series.resample(HistoricalDataBarSize::FiveMins);
+    assert_eq!(five_mins.data.len(), 4);
+    assert_eq!(five_mins.data[0].t_stamp, tz.with_ymd_and_hms(2023, 3, 1, 9, 30, 0).unwrap());
+    assert_eq!(five_mins.data[0].open, 100.0);
+    assert_eq!(five_mins.data[0].high, 105.0);
+    assert_eq!(five_mins.data[0].low, 99.0);
+    assert_eq!(five_mins.data[0].close, 104.5);
+    assert_eq!(five_mins.data[0].volume, Decimal::new(1500, 0));
+    assert_eq!(five_mins.data[0].count, 5);
+    //volume weighted: (100*100 + 101*200 + 102*300 + 103*400 + 104*500) / 1500
+    assert_eq!(five_mins.data[0].wap, Decimal::new(154000, 0) / Decimal::new(1500, 0));
+    assert_eq!(five_mins.data[1].count, 4);
+
+    let sessions = vec![
+        (tz.with_ymd_and_hms(2023, 3, 1, 9, 30, 0).unwrap(), tz.with_ymd_and_hms(2023, 3, 1, 16, 0, 0).unwrap()),
+        (tz.with_ymd_and_hms(2023, 3, 2, 9, 30, 0).unwrap(), tz.with_ymd_and_hms(2023, 3, 2, 16, 0, 0).unwrap()),
+    ];
+    let daily = series.resample_in_sessions(HistoricalDataBarSize::OneDay, &sessions);
+    assert_eq!(daily.data.len(), 2);
+    assert_eq!(daily.data[0].t_stamp, tz.with_ymd_and_hms(2023, 3, 1, 0, 0, 0).unwrap());
+    assert_eq!(daily.data[0].close, 109.5);
+    assert_eq!(daily.data[1].open, 130.0);
+
+    let gaps = series.gaps(HistoricalDataBarSize::OneMin, None);
+    assert_eq!(gaps[0].start, tz.with_ymd_and_hms(2023, 3, 1, 9, 37, 0).unwrap());
+    assert_eq!(gaps[0].missing_bars, 1);
+    let session_gaps = series.gaps(HistoricalDataBarSize::OneMin, Some(&sessions[..1]));
+    assert_eq!(session_gaps.len(), 2);
+    assert_eq!(session_gaps[1].start, tz.with_ymd_and_hms(2023, 3, 1, 9, 40, 0).unwrap());
+    assert_eq!(session_gaps[1].end, tz.with_ymd_and_hms(2023, 3, 1, 16, 0, 0).unwrap());
+    assert_eq!(session_gaps[1].missing_bars, 380);
+}
+
+#[test]
+fn indicators() {
+    let tz = chrono_tz::US::Eastern;
+    let closes = [10.0, 11.0, 12.0, 11.0, 13.0, 14.0, 13.0, 15.0];
+    let bars: Vec<Bar> = closes.iter().enumerate().map(|(i, close)| Bar {
+        t_stamp: tz.with_ymd_and_hms(2023, 3, 1 + i as u32 / 4, 10, i as u32, 0).unwrap(),
+        open: *close, high: close + 1.0, low: close - 1.0, close: *close,
+        wap: Decimal::from_f64(*close).unwrap(),
+        volume: Decimal::new(100 * (i as i64 + 1), 0),
+        count: 1
+    }).collect();
+    let series = BarSeries {start_dt: None, end_dt: None, data: bars.clone()};
+    let close_to = |val: f64, expected: f64| (val - expected).abs() < 1e-9;
+
+    let mut sma = Sma::new(3);
+    assert_eq!(sma.update(&bars[0]), None);
+    assert!(close_to(sma.warm_up(&series).unwrap(), 14.0));
+    assert!(close_to(Ema::new(3).warm_up(&series).unwrap(), 14.0));
+    assert!(close_to(Rsi::new(3).warm_up(&series).unwrap(), 79.88505747126437));
+    assert!(close_to(Atr::new(3).warm_up(&series).unwrap(), 2.432098765432099));
+    let bands = Bollinger::new(3, 2.0).warm_up(&series).unwrap();
+    assert!(close_to(bands.lower, 12.367006838144547) && close_to(bands.middle, 14.0) && close_to(bands.upper, 15.632993161855453));
+    let macd = Macd::new(3, 5, 2).warm_up(&series).unwrap();
+    assert!(close_to(macd.macd, 0.6592592592592599) && close_to(macd.signal, 0.6222222222222226));
+    assert!(close_to(macd.histogram, 0.03703703703703731));
+    //daily VWAP resets on March 2: (13*500 + 14*600 + 13*700 + 15*800) / 2600
+    assert!(close_to(Vwap::new().warm_up(&series).unwrap(), 36000.0 / 2600.0));
+
+    //a bar in progress is replaced by later updates with the same time stamp
+    let mut live = LiveIndicator::new(Sma::new(3));
+    for bar in &bars[..7] {
+        live.update(bar);
+    }
+    let mut in_progress = bars[7].clone();
+    in_progress.close = 20.0;
+    live.update(&in_progress);
+    assert!(close_to(live.update(&bars[7]).unwrap(), 14.0));
+}
+
+#[test]
+fn trading_hours_parsing() {
+    let tz = parse_time_zone("EST").unwrap();
+    assert_eq!(tz, chrono_tz::America::New_York);
+    assert_eq!(parse_time_zone("Europe/Berlin"), Some(chrono_tz::Europe::Berlin));
+    assert_eq!(parse_time_zone("Mars/Olympus"), None);
+
+    let sessions = parse_trading_hours("20230302:CLOSED;20230301:0930-20230301:1200;20230301:1300-20230301:1600", &tz).unwrap();
+    assert_eq!(sessions.len(), 2);
+    assert_eq!(sessions[0].start, tz.with_ymd_and_hms(2023, 3, 1, 9, 30, 0).unwrap());
+    assert_eq!(sessions[1].end, tz.with_ymd_and_hms(2023, 3, 1, 16, 0, 0).unwrap());
+
+    //overnight session across the DST change belongs to the trading date it closes on
+    let overnight = parse_trading_hours("20230311:1700-20230313:1600", &tz).unwrap();
+    assert_eq!(overnight[0].ref_date, chrono::NaiveDate::from_ymd_opt(2023, 3, 13).unwrap());
+    assert_eq!(overnight[0].end - overnight[0].start, Duration::hours(46));
+    //local times in the DST gap are moved forward by the length of the gap
+    let gap = parse_trading_hours("20230312:0230-20230312:0400", &tz).unwrap();
+    assert_eq!(gap[0].start, tz.with_ymd_and_hms(2023, 3, 12, 3, 30, 0).unwrap());
+    let lord_howe = chrono_tz::Australia::Lord_Howe;
+    let gap = parse_trading_hours("20231001:0215-20231001:0400", &lord_howe).unwrap();
+    assert_eq!(gap[0].start, lord_howe.with_ymd_and_hms(2023, 10, 1, 2, 45, 0).unwrap());
+    assert_eq!(gap[0].end - gap[0].start, Duration::minutes(75));
+    //Java's MST has no DST
+    let mst = parse_time_zone("MST").unwrap();
+    assert_eq!(mst, chrono_tz::America::Phoenix);
+    assert_eq!(mst.with_ymd_and_hms(2023, 7, 1, 12, 0, 0).unwrap(), Utc.with_ymd_and_hms(2023, 7, 1, 19, 0, 0).unwrap());
+
+    //legacy format with several segments and an overnight segment
+    let legacy = parse_trading_hours("20090507:0700-1830,1830-0230;20090508:CLOSED", &tz).unwrap();
+    assert_eq!(legacy.len(), 2);
+    assert_eq!(legacy[1].end, tz.with_ymd_and_hms(2009, 5, 8, 2, 30, 0).unwrap());
+    assert_eq!(legacy[1].ref_date, chrono::NaiveDate::from_ymd_opt(2009, 5, 8).unwrap());
+
+    assert!(parse_trading_hours("20230301:0930", &tz).is_err());
+    assert!(parse_trading_hours("2023031:0930-1600", &tz).is_err());
+    assert!(parse_trading_hours("20230301:0930-1675", &tz).is_err());
+    assert!(ContractDetails::default().liquid_sessions().is_err());
+    assert!(ContractDetails::default().liquid_hours().is_none());
+}
+
+#[test]
+fn trading_calendar() {
+    let tz = chrono_tz::US::Eastern;
+    let liquid = parse_trading_hours("20230303:0930-20230303:1600;20230304:CLOSED;20230305:CLOSED;20230306:0930-20230306:1600", &tz).unwrap();
+    let extended = parse_trading_hours("20230303:0400-20230303:2000;20230304:CLOSED;20230305:CLOSED;20230306:0400-20230306:2000", &tz).unwrap();
+    let calendar = TradingCalendar::new(tz, liquid, extended);
+
+    let pre_market = Utc.with_ymd_and_hms(2023, 3, 3, 13, 0, 0).unwrap(); //08:00 New York
+    assert!(!calendar.is_open(&pre_market, true));
+    assert!(calendar.is_open(&pre_market, false));
+    assert_eq!(calendar.time_to_open(&pre_market, true), Some(Duration::minutes(90)));
+    assert_eq!(calendar.time_to_close(&pre_market, false), Some(Duration::hours(12)));
+    assert_eq!(calendar.time_to_close(&pre_market, true), None);
+
+    let friday_close = tz.with_ymd_and_hms(2023, 3, 3, 16, 0, 0).unwrap();
+    assert!(!calendar.is_open(&friday_close, true));
+    assert_eq!(calendar.next_open(&friday_close, true), Some(tz.with_ymd_and_hms(2023, 3, 6, 9, 30, 0).unwrap()));
+    assert_eq!(calendar.next_close(&friday_close, false), Some(tz.with_ymd_and_hms(2023, 3, 3, 20, 0, 0).unwrap()));
+    assert_eq!(calendar.next_open(&tz.with_ymd_and_hms(2023, 3, 6, 10, 0, 0).unwrap(), true), None);
+
+    assert!(calendar.session_for(chrono::NaiveDate::from_ymd_opt(2023, 3, 4).unwrap(), true).is_empty());
+    let monday = calendar.session_for(chrono::NaiveDate::from_ymd_opt(2023, 3, 6).unwrap(), false);
+    assert_eq!(monday.len(), 1);
+    assert_eq!(monday[0].start, tz.with_ymd_and_hms(2023, 3, 6, 4, 0, 0).unwrap());
+}
+
+#[test]
+fn contract_builders() {
+    let spx = Contract::option("SPX", "20231215", Decimal::new(4500, 0), OptionRight::Call, "SMART", "usd").unwrap()
+        .with_trading_class("SPX")
+        .with_multiplier("100");
+    assert_eq!(spx.symbol(), Some("SPX"));
+    assert!(Contract::option_us_smart("AAPL", "202312", Decimal::new(150, 0), OptionRight::Put).is_ok());
+    assert!(Contract::option("SPX", "2023-12-15", Decimal::new(4500, 0), OptionRight::Call, "SMART", "USD").is_err());
+    assert!(Contract::option("SPX", "20231215", Decimal::ZERO, OptionRight::Call, "SMART", "USD").is_err());
+    assert!(Contract::option("SPX", "20231215", Decimal::new(4500, 0), OptionRight::Undefined, "SMART", "USD").is_err());
+    assert!(Contract::future("ES", "202312", "CME", "USD").is_ok());
+    assert!(Contract::future("", "202312", "CME", "USD").is_err());
+    assert!(Contract::future_option("ES", "202312", Decimal::new(4500, 0), OptionRight::Put, "CME", "USD").is_ok());
+
+    let eur_usd = Contract::forex("EUR.USD").unwrap();
+    assert_eq!(eur_usd.symbol(), Some("EUR"));
+    assert!(Contract::forex("EURO.USD").is_err());
+    assert!(Contract::forex("€URUS").is_err());
+    assert!(Contract::forex("ÉUR.US").is_err());
+    assert!(Contract::index("SPX", "CBOE", "USD").is_ok());
+    assert!(Contract::cfd("IBUS500", "SMART", "US Dollar").is_err());
+    assert!(Contract::crypto("BTC", "USD").is_ok());
+    assert!(Contract::bond(SecIdType::Cusip, "912828C57", "SMART", "USD").is_ok());
+    assert!(Contract::bond(SecIdType::Isin, "912828C57", "SMART", "USD").is_err());
+    assert!(Contract::security(SecIdType::Isin, "us0378331005").is_ok());
+    assert!(Contract::security(SecIdType::Isin, "  ").is_err());
+    assert!(Contract::security(SecIdType::Figi, "BBG000B9XRY").is_err());
+}
+
+#[test]
+fn contract_accessors() {
+    let es = Contract::future_option("ES", "202312", Decimal::new(4500, 0), OptionRight::Put, "CME", "USD").unwrap()
+        .with_multiplier("50")
+        .with_local_symbol("ESZ3 P4500");
+    assert_eq!(es.sec_type(), Some(&SecType::OptionOnFuture));
+    assert_eq!(es.last_trade_date_or_contract_month(), Some("202312"));
+    assert_eq!(es.strike(), Some(Decimal::new(4500, 0)));
+    assert_eq!(es.multiplier(), Some(Decimal::new(50, 0)));
+    assert_eq!(es.exchange(), Some("CME"));
+    assert_eq!(es.currency(), Some("USD"));
+    assert_eq!(es.local_symbol(), Some("ESZ3 P4500"));
+    assert!(es.combo_legs().is_empty());
+    assert!(Contract::stock_us_smart("AAPL").with_multiplier("").multiplier().is_none());
+
+    let mut spread = Contract::combo("SPY", "SMART", "USD");
+    spread.add_leg(ComboLeg::new(1, 1, ComboAction::Buy, "SMART"));
+    spread.add_leg(ComboLeg::new(2, 2, ComboAction::Sell, "SMART"));
+    let legs = spread.combo_legs();
+    assert_eq!(legs.len(), 2);
+    assert_eq!((legs[1].con_id(), legs[1].ratio()), (Some(2), Some(2)));
+    assert_eq!(legs[1].exchange(), Some("SMART"));
+}
+
+#[test]
+fn contract_identity() {
+    use std::collections::{BTreeSet, HashMap};
+    let call = Contract::option_us_smart("AAPL", "20250117", Decimal::new(150, 0), OptionRight::Call).unwrap();
+    let same = Contract::option("aapl", "20250117", Decimal::new(15000, 2), OptionRight::Call, "smart", "usd").unwrap()
+        .with_multiplier("100.0");
+    assert_eq!(call, same);
+    assert_ne!(call, Contract::option_us_smart("AAPL", "20250117", Decimal::new(150, 0), OptionRight::Put).unwrap());
+    assert_eq!(Contract::stock_us_smart("AAPL").with_con_id(265598), Contract::stock("AAPL", "NASDAQ", "USD").with_con_id(265598));
+    assert_ne!(Contract::stock_us_smart("AAPL").with_con_id(265598), Contract::stock_us_smart("AAPL"));
+
+    let mut positions = HashMap::new();
+    positions.insert(call.clone(), Decimal::new(2, 0));
+    assert_eq!(positions.get(&same), Some(&Decimal::new(2, 0)));
+    let ordered: BTreeSet<Contract> = vec![Contract::stock_us_smart("MSFT"), Contract::stock_us_smart("AAPL").with_con_id(265598),
+        Contract::stock_us_smart("AAPL")].into_iter().collect();
+    let con_ids: Vec<Option<i32>> = ordered.iter().map(|contract| contract.con_id()).collect();
+    assert_eq!(con_ids, vec![Some(265598), None, None]);
+    assert_eq!(ordered.iter().nth(1).unwrap().symbol(), Some("AAPL"));
+    assert!(Contract::stock_us_smart("aapl") < Contract::stock_us_smart("MSFT"));
+    assert_eq!(Contract::stock_us_smart(" aapl").cmp(&Contract::stock_us_smart("AAPL")), std::cmp::Ordering::Equal);
+
+    assert_eq!(call.to_string(), "AAPL 20250117 150 C");
+    assert_eq!("AAPL 20250117 150 C".parse::<Contract>().unwrap(), call.clone().with_multiplier(""));
+    let ids = ["AAPL", "BRK B", "ES 202312 @CME", "ES 202312 4500 P FOP @CME", "EUR CASH JPY", "SAP @IBIS EUR",
+        "CUSIP:912828C57 BOND", "BTC CRYPTO"];
+    for id in ids {
+        assert_eq!(id.parse::<Contract>().unwrap().to_string(), id);
+    }
+    assert_eq!(Contract::forex("EUR.USD").unwrap().to_string(), "EUR CASH");
+    assert!("".parse::<Contract>().is_err());
+    assert!("AAPL 20250117 0 C".parse::<Contract>().is_err());
+    assert!("ISIN:123 BOND".parse::<Contract>().is_err());
+}
+
+#[test]
+fn option_chain_filters() {
+    let params = |exchange: &str, strikes: &[i64], expirations: &[&str]| OptParams {
+        underlying_con_id: Some("756733".to_string()),
+        exchange: Some(exchange.to_string()),
+        trading_class: Some("SPY".to_string()),
+        multiplier: Some("100".to_string()),
+        strikes: strikes.iter().map(|strike| Decimal::new(*strike, 0)).collect(),
+        expirations: expirations.iter().map(|expiry| expiry.to_string()).collect()
+    };
+    let chain = OptionChain::new(Contract::stock_us_smart("SPY"), vec![
+        params("CBOE", &[380, 390], &["20230317"]),
+        params("SMART", &[370, 380, 390, 400, 410, 420], &["20230303", "20230317", "20230616"])
+    ]);
+    //only the SMART entry is used for the grid
+    assert_eq!(chain.strikes().len(), 6);
+    assert_eq!(chain.expirations(), vec!["20230303", "20230317", "20230616"]);
+    assert_eq!(chain.contracts().len(), 2 * 3 * 6);
+
+    let today = chrono::NaiveDate::from_ymd_opt(2023, 3, 1).unwrap();
+    let spot = Decimal::new(396, 0);
+    let near = chain.clone().dte_range(today, 7, 60).strikes_around(spot, 3);
+    assert_eq!(near.expirations(), vec!["20230317"]);
+    assert_eq!(near.strikes(), vec![Decimal::new(390, 0), Decimal::new(400, 0), Decimal::new(410, 0)]);
+    assert_eq!(near.contracts().len(), 6);
+    let otm = chain.moneyness(spot, Decimal::new(100, 2), Decimal::new(105, 2));
+    assert_eq!(otm.strikes(), vec![Decimal::new(400, 0), Decimal::new(410, 0)]);
+    assert!(otm.contract("20230317", Decimal::new(400, 0), OptionRight::Put).is_some());
+    assert!(otm.contract("20230317", Decimal::new(390, 0), OptionRight::Put).is_none());
+}
+
+#[test]
+fn futures_rolls() {
+    let date = |month: u32, day: u32| chrono::NaiveDate::from_ymd_opt(2023, month, day).unwrap();
+    let future = |con_id: i32, month: &str, last_trade_date: chrono::NaiveDate| ListedFuture {
+        last_trade_date,
+        contract: Contract::future("ES", month, "CME", "USD").unwrap().with_con_id(con_id)
+    };
+    let chain = FuturesChain::new(vec![
+        future(3, "202309", date(9, 15)),
+        future(1, "202303", date(3, 17)),
+        future(2, "202306", date(6, 16)),
+    ]);
+    assert_eq!(chain.expiries(), vec![date(3, 17), date(6, 16), date(9, 15)]);
+    assert_eq!(chain.front(date(3, 9), 8).unwrap().contract.con_id(), Some(2));
+    assert_eq!(chain.front(date(3, 8), 8).unwrap().contract.con_id(), Some(1));
+    assert_eq!(chain.next(date(3, 8), 8).unwrap().contract.con_id(), Some(2));
+    let rolls = chain.roll_dates(8);
+    assert_eq!(rolls.len(), 2);
+    assert_eq!(rolls[0].date, date(3, 9));
+
+    //daily bars: the June contract overtakes the March contract in volume on March 10th
+    let tz = chrono_tz::US::Central;
+    let daily = |closes: &[(u32, f64, i64)]| BarSeries {start_dt: None, end_dt: None, data: closes.iter().map(|(day, close, volume)| Bar {
+        t_stamp: tz.with_ymd_and_hms(2023, 3, *day, 0, 0, 0).unwrap(),
+        open: *close, high: *close, low: *close, close: *close,
+        wap: Decimal::ZERO,
+        volume: Decimal::new(*volume, 0),
+        count: 1
+    }).collect()};
+    let mut bars = std::collections::HashMap::new();
+    bars.insert(1, daily(&[(8, 4000.0, 900), (9, 4010.0, 800), (10, 4020.0, 300), (13, 4030.0, 100)]));
+    bars.insert(2, daily(&[(8, 4040.0, 500), (9, 4050.0, 700), (10, 4060.0, 900), (13, 4070.0, 1000)]));
+    let volumes = bars.iter().map(|(con_id, series)| (*con_id, series.data[0].volume)).collect();
+    assert_eq!(chain.front_by_volume(&volumes).unwrap().contract.con_id(), Some(1));
+    let volume_rolls = chain.volume_roll_dates(&bars);
+    assert_eq!(volume_rolls[0].date, date(3, 10));
+    assert_eq!(volume_rolls[1].date, date(6, 16));
+
+    //the March contract is shifted by the difference of the closes on March 9th
+    let stitched = FuturesChain::stitch_back_adjusted(&volume_rolls[..1], &bars);
+    let closes: Vec<f64> = stitched.data.iter().map(|bar| bar.close).collect();
+    assert_eq!(closes, vec![4040.0, 4050.0, 4060.0, 4070.0]);
+}
+
+#[test]
+fn option_strategies() {
+    let spy = Contract::stock_us_smart("SPY");
+    let strike = |strike: i64| Decimal::new(strike, 0);
+    let condor = OptionStrategy::iron_condor(&spy, "20230317", strike(380), strike(390), strike(410), strike(420)).unwrap();
+    let actions: Vec<bool> = condor.legs.iter().map(|leg| matches!(leg.action, ComboAction::Buy)).collect();
+    assert_eq!(actions, vec![true, false, false, true]);
+    assert!(matches!(condor.legs[2].right, OptionRight::Call));
+    assert!(OptionStrategy::iron_condor(&spy, "20230317", strike(390), strike(380), strike(410), strike(420)).is_err());
+
+    let butterfly = OptionStrategy::butterfly(&spy, "20230317", strike(390), strike(400), strike(410), OptionRight::Call).unwrap();
+    assert_eq!(butterfly.legs.iter().map(|leg| leg.ratio).collect::<Vec<i32>>(), vec![1, 2, 1]);
+    let calendar = OptionStrategy::calendar(&spy, "20230317", "20230421", strike(400), OptionRight::Put).unwrap();
+    assert!(matches!(calendar.legs[0].action, ComboAction::Sell));
+    assert!(OptionStrategy::calendar(&spy, "20230421", "20230317", strike(400), OptionRight::Put).is_err());
+    assert!(OptionStrategy::strangle(&spy, "20230317", strike(410), strike(390)).is_err());
+    assert!(OptionStrategy::ratio_spread(&spy, "20230317", strike(400), 1, strike(410), 0, OptionRight::Call).is_err());
+    assert_eq!(OptionStrategy::straddle(&spy, "20230317", strike(400)).unwrap().leg_contracts().len(), 2);
+    assert!(OptionStrategy::vertical(&spy, "20230317", strike(400), strike(410), OptionRight::Call).unwrap()
+        .with_trading_class("SPY").leg_contracts().iter().all(|leg| leg.symbol() == Some("SPY")));
+
+    let order = Order::limit(Contract::combo("SPY", "SMART", "USD"), Action::Buy, Decimal::ONE, Decimal::new(150, 2), TimeInForce::Day)
+        .combo()
+        .smart_combo_routing_param("LeginPrio", "0")
+        .combo();
+    assert_eq!(order.smart_combo_routing_params(), &[("NonGuaranteed".to_string(), "1".to_string()), ("LeginPrio".to_string(), "0".to_string())]);
+    let order = order.smart_combo_routing_param("NonGuaranteed", "0");
+    assert_eq!(order.smart_combo_routing_params()[0].1, "0");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_round_trip() {
+    let contract = Contract::option_us_smart("AAPL", "20231215", Decimal::new(150, 0), OptionRight::Call).unwrap();
+    let order = Order::limit(contract, Action::Buy, Decimal::new(2, 0), Decimal::new(345, 2), TimeInForce::Day);
+    let json = serde_json::to_string(&order).unwrap();
+    let parsed: Order = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_string(&parsed).unwrap(), json);
+
+    let series = sample_bars();
+    let json = serde_json::to_value(&series).unwrap();
+    assert_eq!(json["data"][0]["t_stamp"], serde_json::json!("2023-03-01T09:30:00-05:00 US/Eastern"));
+    let parsed: BarSeries = serde_json::from_value(json).unwrap();
+    assert_eq!(parsed.data[0].t_stamp, series.data[0].t_stamp);
+    assert_eq!(parsed.data[0].t_stamp.timezone(), chrono_tz::US::Eastern);
+    assert_eq!(parsed.data[1].volume, series.data[1].volume);
+}